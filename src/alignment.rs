@@ -0,0 +1,444 @@
+use crate::metric::StringMetric;
+use std::ops::Range;
+
+/*
+    Global (Needleman-Wunsch) and local (Smith-Waterman) alignment with affine gaps (Gotoh), over chars or
+    any token slice. Local alignment finds the best matching region of both sequences, e.g. a short form
+    label ("Policy No") inside a long OCR line ("Insured: J. Smith Po1icy No: 44-1024 Date: ...").
+
+    References:
+
+    * [Wikipedia Needleman-Wunsch](https://en.wikipedia.org/wiki/Needleman%E2%80%93Wunsch_algorithm)
+    * [Wikipedia Smith-Waterman](https://en.wikipedia.org/wiki/Smith%E2%80%93Waterman_algorithm)
+    * [Gotoh, An improved algorithm for matching biological sequences](https://doi.org/10.1016/0022-2836(82)90398-9)
+*/
+
+/// Scoring for alignments: aligned elements add `match_score` when equal and `mismatch` (usually negative)
+/// when not; a gap of length L costs `gap_open + (L - 1) * gap_extend`, so one long gap is cheaper than
+/// several short ones when `gap_extend < gap_open`. Gap costs are positive and subtracted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scoring {
+    pub match_score: f64,
+    pub mismatch: f64,
+    pub gap_open: f64,
+    pub gap_extend: f64,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Scoring {
+            match_score: 2.0,
+            mismatch: -1.0,
+            gap_open: 2.0,
+            gap_extend: 1.0,
+        }
+    }
+}
+
+impl Scoring {
+    fn pair<T: Eq>(&self, x: &T, y: &T) -> f64 {
+        if x == y {
+            self.match_score
+        } else {
+            self.mismatch
+        }
+    }
+}
+
+/// Alignment is an alignment of `a[a_range]` with `b[b_range]` and its score. `pairs` uses the convention of
+/// `edit_distance::levenshtein_alignment`: `(Some(i), Some(j))` aligns `a[i]` with `b[j]`, `(Some(i), None)`
+/// is a gap in `b` and `(None, Some(j))` a gap in `a`. Global alignments cover both sequences entirely.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alignment {
+    pub score: f64,
+    pub pairs: Vec<(Option<usize>, Option<usize>)>,
+    pub a_range: Range<usize>,
+    pub b_range: Range<usize>,
+}
+
+type Pairs = Vec<(Option<usize>, Option<usize>)>;
+
+/// State is the last column of an alignment: an aligned pair, a gap in `b` or a gap in `a`.
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Pair,
+    GapB,
+    GapA,
+}
+
+/// Gotoh holds the three dp matrices, each cell the best score of an alignment of `a[..i]` and `b[..j]`
+/// ending in that state.
+struct Gotoh {
+    cols: usize,
+    pair: Vec<f64>,
+    gap_b: Vec<f64>,
+    gap_a: Vec<f64>,
+}
+
+impl Gotoh {
+    fn idx(&self, i: usize, j: usize) -> usize {
+        i * self.cols + j
+    }
+
+    fn get(&self, state: State, i: usize, j: usize) -> f64 {
+        let k = self.idx(i, j);
+        match state {
+            State::Pair => self.pair[k],
+            State::GapB => self.gap_b[k],
+            State::GapA => self.gap_a[k],
+        }
+    }
+
+    /// best: the state with the highest score at (i, j); ties prefer Pair, then GapB.
+    fn best(&self, i: usize, j: usize) -> (State, f64) {
+        [State::Pair, State::GapB, State::GapA]
+            .iter()
+            .map(|&s| (s, self.get(s, i, j)))
+            .fold((State::Pair, f64::NEG_INFINITY), |acc, x| {
+                if x.1 > acc.1 {
+                    x
+                } else {
+                    acc
+                }
+            })
+    }
+
+    /// fill: the matrices for a global alignment, or a local one where an aligned pair may start anew.
+    fn fill<T: Eq>(a: &[T], b: &[T], scoring: &Scoring, local: bool) -> Self {
+        let cols = b.len() + 1;
+        let size = (a.len() + 1) * cols;
+        let mut g = Gotoh {
+            cols,
+            pair: vec![f64::NEG_INFINITY; size],
+            gap_b: vec![f64::NEG_INFINITY; size],
+            gap_a: vec![f64::NEG_INFINITY; size],
+        };
+        let gap = |len: usize| -(scoring.gap_open + (len - 1) as f64 * scoring.gap_extend);
+        g.pair[0] = 0.0;
+        if !local {
+            for i in 1..=a.len() {
+                let k = g.idx(i, 0);
+                g.gap_b[k] = gap(i);
+            }
+            for j in 1..=b.len() {
+                let k = g.idx(0, j);
+                g.gap_a[k] = gap(j);
+            }
+        }
+        for i in 1..=a.len() {
+            for j in 1..=b.len() {
+                let k = g.idx(i, j);
+                let mut prev = g.best(i - 1, j - 1).1;
+                if local {
+                    prev = prev.max(0.0);
+                }
+                g.pair[k] = prev + scoring.pair(&a[i - 1], &b[j - 1]);
+
+                let up = g.idx(i - 1, j);
+                g.gap_b[k] = (g.pair[up] - scoring.gap_open)
+                    .max(g.gap_b[up] - scoring.gap_extend)
+                    .max(g.gap_a[up] - scoring.gap_open);
+
+                let left = g.idx(i, j - 1);
+                g.gap_a[k] = (g.pair[left] - scoring.gap_open)
+                    .max(g.gap_a[left] - scoring.gap_extend)
+                    .max(g.gap_b[left] - scoring.gap_open);
+            }
+        }
+        g
+    }
+
+    /// traceback: the pairs of the alignment ending in `state` at (i, j), and where it starts.
+    /// A local alignment stops at the first pair not extending a positive scoring prefix.
+    fn traceback(
+        &self,
+        scoring: &Scoring,
+        mut state: State,
+        mut i: usize,
+        mut j: usize,
+        local: bool,
+    ) -> (Pairs, usize, usize) {
+        let mut pairs = Vec::new();
+        while i > 0 || j > 0 {
+            let score = self.get(state, i, j);
+            match state {
+                State::Pair => {
+                    pairs.push((Some(i - 1), Some(j - 1)));
+                    let (prev, best) = self.best(i - 1, j - 1);
+                    i -= 1;
+                    j -= 1;
+                    if local && best <= 0.0 {
+                        break;
+                    }
+                    state = prev;
+                }
+                State::GapB => {
+                    pairs.push((Some(i - 1), None));
+                    i -= 1;
+                    state = if self.get(State::GapB, i, j) - scoring.gap_extend == score {
+                        State::GapB
+                    } else if self.get(State::Pair, i, j) - scoring.gap_open == score {
+                        State::Pair
+                    } else {
+                        State::GapA
+                    };
+                }
+                State::GapA => {
+                    pairs.push((None, Some(j - 1)));
+                    j -= 1;
+                    state = if self.get(State::GapA, i, j) - scoring.gap_extend == score {
+                        State::GapA
+                    } else if self.get(State::Pair, i, j) - scoring.gap_open == score {
+                        State::Pair
+                    } else {
+                        State::GapB
+                    };
+                }
+            }
+            // the empty prefix of a global alignment
+            if i == 0 && j == 0 {
+                break;
+            }
+        }
+        pairs.reverse();
+        (pairs, i, j)
+    }
+}
+
+/// needleman_wunsch: best global alignment of all of `a` with all of `b` under `scoring`. higher score is more similar.
+pub fn needleman_wunsch<T: Eq>(a: &[T], b: &[T], scoring: &Scoring) -> Alignment {
+    let g = Gotoh::fill(a, b, scoring, false);
+    let (state, score) = g.best(a.len(), b.len());
+    let (pairs, _, _) = g.traceback(scoring, state, a.len(), b.len(), false);
+    Alignment {
+        score,
+        pairs,
+        a_range: 0..a.len(),
+        b_range: 0..b.len(),
+    }
+}
+
+/// smith_waterman: best local alignment, the highest scoring pair of regions of `a` and `b` under `scoring`;
+/// the score is 0.0 and the alignment empty when no elements match. higher score is more similar.
+/// Ties go to the region ending first in `a`, then in `b`.
+pub fn smith_waterman<T: Eq>(a: &[T], b: &[T], scoring: &Scoring) -> Alignment {
+    let g = Gotoh::fill(a, b, scoring, true);
+    let (mut best, mut end) = (0.0, (0, 0));
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            // a best local alignment always ends on an aligned pair
+            let score = g.get(State::Pair, i, j);
+            if score > best {
+                best = score;
+                end = (i, j);
+            }
+        }
+    }
+    if best <= 0.0 {
+        return Alignment {
+            score: 0.0,
+            pairs: Vec::new(),
+            a_range: 0..0,
+            b_range: 0..0,
+        };
+    }
+    let (pairs, i, j) = g.traceback(scoring, State::Pair, end.0, end.1, true);
+    Alignment {
+        score: best,
+        pairs,
+        a_range: i..end.0,
+        b_range: j..end.1,
+    }
+}
+
+/// needleman_wunsch_similarity: global alignment score of the chars of `s1` and `s2` over the score of
+/// matching the longer string with itself, negative scores as 0.0. higher score is more similar.
+pub fn needleman_wunsch_similarity(s1: &str, s2: &str, scoring: &Scoring) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+    (needleman_wunsch(&a, &b, scoring).score / (scoring.match_score * max_len as f64))
+        .clamp(0.0, 1.0)
+}
+
+/// smith_waterman_similarity: local alignment score of the chars of `s1` and `s2` over the score of matching
+/// the shorter string with itself, 1.0 when the shorter string occurs exactly inside the longer one.
+/// higher score is more similar.
+pub fn smith_waterman_similarity(s1: &str, s2: &str, scoring: &Scoring) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => return 1.0,
+        (true, false) | (false, true) => return 0.0,
+        _ => {}
+    }
+    let min_len = a.len().min(b.len());
+    (smith_waterman(&a, &b, scoring).score / (scoring.match_score * min_len as f64)).clamp(0.0, 1.0)
+}
+
+/// NeedlemanWunsch implements `StringMetric` with `needleman_wunsch` over chars; the raw score is the
+/// alignment score, similarity is `needleman_wunsch_similarity`.
+pub struct NeedlemanWunsch {
+    pub scoring: Scoring,
+}
+
+/// SmithWaterman implements `StringMetric` with `smith_waterman` over chars; the raw score is the
+/// alignment score, similarity is `smith_waterman_similarity`.
+pub struct SmithWaterman {
+    pub scoring: Scoring,
+}
+
+impl StringMetric for NeedlemanWunsch {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+        needleman_wunsch(&a, &b, &self.scoring).score
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        needleman_wunsch_similarity(s1, s2, &self.scoring)
+    }
+}
+
+impl StringMetric for SmithWaterman {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+        smith_waterman(&a, &b, &self.scoring).score
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        smith_waterman_similarity(s1, s2, &self.scoring)
+    }
+}
+
+#[allow(clippy::float_cmp)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    /// score: recomputes the score of `pairs` under `scoring`.
+    fn score<T: Eq>(
+        a: &[T],
+        b: &[T],
+        pairs: &[(Option<usize>, Option<usize>)],
+        scoring: &Scoring,
+    ) -> f64 {
+        let mut total = 0.0;
+        let mut prev_gap: Option<bool> = None;
+        for &p in pairs {
+            match p {
+                (Some(i), Some(j)) => {
+                    total += scoring.pair(&a[i], &b[j]);
+                    prev_gap = None;
+                }
+                (Some(_), None) | (None, Some(_)) => {
+                    let in_b = p.1.is_none();
+                    total -= if prev_gap == Some(in_b) {
+                        scoring.gap_extend
+                    } else {
+                        scoring.gap_open
+                    };
+                    prev_gap = Some(in_b);
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+        total
+    }
+
+    #[test]
+    fn on_needleman_wunsch() {
+        let s = Scoring {
+            match_score: 1.0,
+            mismatch: -1.0,
+            gap_open: 1.0,
+            gap_extend: 1.0,
+        };
+        // linear gaps: GATTACA / GCATGCU scores 0 in the classic example
+        let (a, b) = (chars("GATTACA"), chars("GCATGCU"));
+        let al = needleman_wunsch(&a, &b, &s);
+        assert_eq!(al.score, 0.0);
+        assert_eq!(score(&a, &b, &al.pairs, &s), al.score);
+        assert_eq!(al.pairs.iter().filter(|p| p.0.is_some()).count(), a.len());
+        assert_eq!(al.pairs.iter().filter(|p| p.1.is_some()).count(), b.len());
+
+        let empty: Vec<char> = Vec::new();
+        let al = needleman_wunsch(&a, &empty, &Scoring::default());
+        assert_eq!(al.score, -(2.0 + 6.0));
+        assert_eq!(al.pairs.len(), 7);
+        assert_eq!(needleman_wunsch(&empty, &empty, &s).score, 0.0);
+    }
+
+    #[test]
+    fn on_affine_gaps() {
+        let s = Scoring::default();
+        // one gap of 3 (-4) beats two separate gaps (-2 -2 -1)
+        let (a, b) = (chars("ABCXXXDEF"), chars("ABCDEF"));
+        let al = needleman_wunsch(&a, &b, &s);
+        assert_eq!(al.score, 6.0 * 2.0 - 4.0);
+        assert_eq!(score(&a, &b, &al.pairs, &s), al.score);
+        let gaps: Vec<usize> = al
+            .pairs
+            .iter()
+            .filter(|p| p.1.is_none())
+            .map(|p| p.0.unwrap())
+            .collect();
+        assert_eq!(gaps, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn on_smith_waterman_label_in_line() {
+        let s = Scoring::default();
+        let (label, line) = (
+            chars("Policy No"),
+            chars("Insured: J. Smith Po1icy No: 44-1024 Date: 2019-03-01"),
+        );
+        let al = smith_waterman(&label, &line, &s);
+        assert_eq!(al.a_range, 0..9);
+        assert_eq!(al.b_range, 18..27);
+        assert_eq!(al.score, 8.0 * 2.0 - 1.0);
+        assert_eq!(score(&label, &line, &al.pairs, &s), al.score);
+        let found: String = line[al.b_range.clone()].iter().collect();
+        assert_eq!(found, "Po1icy No");
+
+        let sim =
+            smith_waterman_similarity("Policy No", "Insured: J. Smith Po1icy No: 44-1024", &s);
+        // 8 matches and one mismatch out of 9 matches
+        assert_eq!(sim, 15.0 / 18.0);
+        assert_eq!(
+            smith_waterman_similarity("Date", "Insured Date: 2019", &s),
+            1.0
+        );
+        assert_eq!(smith_waterman_similarity("xyz", "abc", &s), 0.0);
+        assert_eq!(
+            smith_waterman(&chars("xyz"), &chars("abc"), &s).pairs,
+            vec![]
+        );
+    }
+
+    #[test]
+    fn on_token_alignment() {
+        let a = vec!["intercontinental", "amstel", "amsterdam"];
+        let b = vec!["hotel", "amstel", "amsterdam", "netherlands"];
+        let al = smith_waterman(&a, &b, &Scoring::default());
+        assert_eq!(al.pairs, vec![(Some(1), Some(1)), (Some(2), Some(2))]);
+        assert_eq!(al.score, 4.0);
+    }
+
+    #[test]
+    fn on_alignment_metrics() {
+        let nw = NeedlemanWunsch {
+            scoring: Scoring::default(),
+        };
+        let sw = SmithWaterman {
+            scoring: Scoring::default(),
+        };
+        assert_eq!(nw.similarity("Zürich", "Zürich"), 1.0);
+        assert_eq!(nw.score("Zürich", "Zurich"), 5.0 * 2.0 - 1.0);
+        assert_eq!(sw.distance("Amstel", "InterContinental Amstel"), 0.0);
+        assert!(nw.similarity("Amstel", "InterContinental Amstel") < 0.5);
+        assert_eq!(nw.similarity("", ""), 1.0);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::edit_distance::levenshtein_alignment;
+use crate::weighted_edit::CostTable;
+use std::collections::HashMap;
+
+/// ConfusionCounts tallies the char edits seen when aligning (OCR output, corrected value) pairs,
+/// and turns them into a `CostTable` of negative log probabilities for `weighted_levenshtein_distance`.
+///
+/// Costs are for editing the OCR output (source, `s1`) into the corrected value (target, `s2`):
+///
+/// * substitution `a` -> `b`: `-ln P(b | a read)`, over every aligned occurrence of OCR char `a`
+/// * deletion of `a`: `-ln P(a is spurious | a read)`, over every aligned occurrence of OCR char `a`
+/// * insertion of `b`: `-ln P(b was dropped | b expected)`, over every occurrence of corrected char `b`
+///
+/// Pairs are aligned with unit cost Levenshtein, so multi-char confusions (`rn` -> `m`) are learned
+/// as a substitution plus an insertion/deletion; add them with `CostTable::add_multi_substitution` if needed.
+///
+/// References:
+///
+/// * [A Spelling Correction Program Based on a Noisy Channel Model](https://www.aclweb.org/anthology/C90-2036)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ConfusionCounts {
+    pub substitutions: HashMap<(char, char), usize>,
+    pub insertions: HashMap<char, usize>,
+    pub deletions: HashMap<char, usize>,
+    /// occurrences of each char in the OCR output, the denominator for substitutions and deletions.
+    pub source_chars: HashMap<char, usize>,
+    /// occurrences of each char in the corrected values, the denominator for insertions.
+    pub target_chars: HashMap<char, usize>,
+    pub pairs: usize,
+}
+
+impl ConfusionCounts {
+    pub fn new() -> Self {
+        ConfusionCounts::default()
+    }
+
+    /// from_pairs: counts over every (OCR output, corrected value) pair.
+    pub fn from_pairs<'a, I>(pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a str, &'a str)>,
+    {
+        let mut counts = ConfusionCounts::new();
+        for (ocr, truth) in pairs {
+            counts.add_pair(ocr, truth);
+        }
+        counts
+    }
+
+    /// add_pair: aligns `ocr` to `truth` and adds the edits to the counts.
+    pub fn add_pair(&mut self, ocr: &str, truth: &str) {
+        let (a, b): (Vec<char>, Vec<char>) = (ocr.chars().collect(), truth.chars().collect());
+        for &c in &a {
+            *self.source_chars.entry(c).or_insert(0) += 1;
+        }
+        for &c in &b {
+            *self.target_chars.entry(c).or_insert(0) += 1;
+        }
+        for op in levenshtein_alignment(&a, &b) {
+            match op {
+                (Some(i), Some(j)) if a[i] != b[j] => {
+                    *self.substitutions.entry((a[i], b[j])).or_insert(0) += 1
+                }
+                (Some(i), None) => *self.deletions.entry(a[i]).or_insert(0) += 1,
+                (None, Some(j)) => *self.insertions.entry(b[j]).or_insert(0) += 1,
+                _ => {}
+            }
+        }
+        self.pairs += 1;
+    }
+
+    /// cost_table: negative log probabilities with add-`alpha` smoothing, `-ln((count + alpha) / (total + alpha))`.
+    /// Unseen edits fall back to the table defaults, the cost of an edit with count 0 over all source
+    /// (substitution, deletion) or target (insertion) chars, so they always cost more than any observed edit.
+    /// `alpha` must be > 0.
+    pub fn cost_table(&self, alpha: f64) -> CostTable {
+        let cost =
+            |count: usize, total: usize| -((count as f64 + alpha) / (total as f64 + alpha)).ln();
+        let source_total: usize = self.source_chars.values().sum();
+        let target_total: usize = self.target_chars.values().sum();
+
+        let mut table = CostTable::new(
+            cost(0, source_total),
+            cost(0, target_total),
+            cost(0, source_total),
+        );
+        for (&(a, b), &n) in &self.substitutions {
+            table.set_substitution(a, b, cost(n, self.source_chars[&a]));
+        }
+        for (&a, &n) in &self.deletions {
+            table.set_deletion(a, cost(n, self.source_chars[&a]));
+        }
+        for (&b, &n) in &self.insertions {
+            table.set_insertion(b, cost(n, self.target_chars[&b]));
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::weighted_edit::{weighted_levenshtein_distance, CostModel};
+
+    fn pairs() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("P0LICY", "POLICY"),
+            ("CLAIM 5O1", "CLAIM 501"),
+            ("1NSURED", "INSURED"),
+            ("AMOUNT: 1OO", "AMOUNT: 100"),
+            ("DATE.", "DATE"),
+            ("ADRESS", "ADDRESS"),
+        ]
+    }
+
+    #[test]
+    fn on_confusion_counts() {
+        let c = ConfusionCounts::from_pairs(pairs());
+        assert_eq!(c.pairs, 6);
+        assert_eq!(c.substitutions[&('0', 'O')], 1);
+        assert_eq!(c.substitutions[&('O', '0')], 3);
+        assert_eq!(c.substitutions[&('1', 'I')], 1);
+        assert_eq!(c.deletions[&'.'], 1);
+        assert_eq!(c.insertions[&'D'], 1);
+        assert_eq!(c.source_chars[&'O'], 4);
+    }
+
+    #[test]
+    fn on_learned_cost_table() {
+        let t = ConfusionCounts::from_pairs(pairs()).cost_table(1.0);
+        // observed confusions are cheaper than unseen ones
+        assert!(t.substitution_cost('O', '0') < t.substitution_cost('O', 'Q'));
+        assert!(t.deletion_cost('.') < t.deletion_cost('A'));
+        assert!(t.substitution_cost('O', '0') > 0.0);
+        assert!(
+            weighted_levenshtein_distance("CLAIM 1O2", "CLAIM 102", &t)
+                < weighted_levenshtein_distance("CLAIM X02", "CLAIM 102", &t)
+        );
+    }
+}
@@ -0,0 +1,195 @@
+use crate::metric::StringMetric;
+use crate::ngram::SparseProfile;
+
+/*
+    Distributional distances between n-gram frequency profiles, comparing strings as
+    probability distributions over grams rather than as sets of grams.
+
+    References:
+
+    * [Wikipedia Jensen-Shannon divergence](https://en.wikipedia.org/wiki/Jensen%E2%80%93Shannon_divergence)
+    * [Wikipedia Kullback-Leibler divergence](https://en.wikipedia.org/wiki/Kullback%E2%80%93Leibler_divergence)
+    * [Wikipedia Hellinger distance](https://en.wikipedia.org/wiki/Hellinger_distance)
+    * [Wikipedia Bhattacharyya distance](https://en.wikipedia.org/wiki/Bhattacharyya_distance)
+*/
+
+/// jensen_shannon_divergence: mean KL of `p` and `q` to their mixture m = (p + q) / 2,
+/// in bits so it is within [0,1]. higher score is less similar.
+/// Two empty profiles are identical (0.0); one empty profile is maximally divergent (1.0).
+pub fn jensen_shannon_divergence(p: &SparseProfile, q: &SparseProfile) -> f64 {
+    match (p.total, q.total) {
+        (0, 0) => return 0.0,
+        (0, _) | (_, 0) => return 1.0,
+        _ => {}
+    }
+    let (p_total, q_total) = (p.total as f64, q.total as f64);
+    let mut jsd = 0.0;
+    p.merge(q, |pc, qc| {
+        let (pi, qi) = (pc as f64 / p_total, qc as f64 / q_total);
+        let mi = (pi + qi) / 2.0;
+        if pi > 0.0 {
+            jsd += 0.5 * pi * (pi / mi).log2();
+        }
+        if qi > 0.0 {
+            jsd += 0.5 * qi * (qi / mi).log2();
+        }
+    });
+    // guard float error just outside [0,1]
+    jsd.clamp(0.0, 1.0)
+}
+
+/// jensen_shannon_distance: sqrt of jensen_shannon_divergence, a metric within [0,1]. higher score is less similar.
+pub fn jensen_shannon_distance(p: &SparseProfile, q: &SparseProfile) -> f64 {
+    jensen_shannon_divergence(p, q).sqrt()
+}
+
+/// kullback_leibler_divergence: KL(p || q) in nats with additive smoothing: every gram in the union of both
+/// profiles gets `epsilon` added to its count before normalizing, so grams missing from `q` do not make it infinite.
+/// Asymmetric and unbounded. higher score is less similar. `epsilon` must be > 0.
+pub fn kullback_leibler_divergence(p: &SparseProfile, q: &SparseProfile, epsilon: f64) -> f64 {
+    let vocab = p.union_len(q);
+    if vocab == 0 {
+        return 0.0;
+    }
+    let v = vocab as f64;
+    let (p_total, q_total) = (p.total as f64 + epsilon * v, q.total as f64 + epsilon * v);
+    let mut kl = 0.0;
+    p.merge(q, |pc, qc| {
+        let pi = (pc as f64 + epsilon) / p_total;
+        let qi = (qc as f64 + epsilon) / q_total;
+        kl += pi * (pi / qi).ln();
+    });
+    kl.max(0.0)
+}
+
+/// bhattacharyya_coefficient: sum of sqrt(p_i * q_i), the overlap of `p` and `q` within [0,1].
+/// higher score is more similar. Two empty profiles are identical (1.0).
+pub fn bhattacharyya_coefficient(p: &SparseProfile, q: &SparseProfile) -> f64 {
+    match (p.total, q.total) {
+        (0, 0) => return 1.0,
+        (0, _) | (_, 0) => return 0.0,
+        _ => {}
+    }
+    // only grams present in both contribute
+    let (p_total, q_total) = (p.total as f64, q.total as f64);
+    let mut bc = 0.0;
+    p.merge(q, |pc, qc| {
+        bc += (pc as f64 / p_total * qc as f64 / q_total).sqrt()
+    });
+    bc.min(1.0)
+}
+
+/// bhattacharyya_distance: -ln(bhattacharyya_coefficient), infinite when `p` and `q` share no grams.
+/// higher score is less similar.
+pub fn bhattacharyya_distance(p: &SparseProfile, q: &SparseProfile) -> f64 {
+    -bhattacharyya_coefficient(p, q).ln()
+}
+
+/// hellinger_distance: sqrt(1 - bhattacharyya_coefficient), a metric within [0,1]. higher score is less similar.
+pub fn hellinger_distance(p: &SparseProfile, q: &SparseProfile) -> f64 {
+    (1.0 - bhattacharyya_coefficient(p, q)).max(0.0).sqrt()
+}
+
+/// JensenShannon implements `StringMetric` with 1 - `jensen_shannon_distance` over n-gram profiles of size `n`;
+/// the raw score is the divergence.
+pub struct JensenShannon {
+    pub n: usize,
+}
+
+/// Hellinger implements `StringMetric` with 1 - `hellinger_distance` over n-gram profiles of size `n`;
+/// the raw score is the distance.
+pub struct Hellinger {
+    pub n: usize,
+}
+
+impl StringMetric for JensenShannon {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        jensen_shannon_divergence(
+            &SparseProfile::from_str(s1, self.n),
+            &SparseProfile::from_str(s2, self.n),
+        )
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        1.0 - self.distance(s1, s2)
+    }
+    fn distance(&self, s1: &str, s2: &str) -> f64 {
+        self.score(s1, s2).sqrt()
+    }
+}
+
+impl StringMetric for Hellinger {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        hellinger_distance(
+            &SparseProfile::from_str(s1, self.n),
+            &SparseProfile::from_str(s2, self.n),
+        )
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        1.0 - self.score(s1, s2)
+    }
+    fn distance(&self, s1: &str, s2: &str) -> f64 {
+        self.score(s1, s2)
+    }
+}
+
+#[allow(clippy::float_cmp)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn on_identical_profiles() {
+        let p = SparseProfile::from_str("black leather wallet", 2);
+        assert!(close(jensen_shannon_divergence(&p, &p), 0.0));
+        assert!(close(kullback_leibler_divergence(&p, &p, 0.5), 0.0));
+        assert!(close(bhattacharyya_coefficient(&p, &p), 1.0));
+        // sqrt amplifies float error in 1 - bc
+        assert!(hellinger_distance(&p, &p) < 1e-6);
+        assert!(close(bhattacharyya_distance(&p, &p), 0.0));
+    }
+
+    #[test]
+    fn on_disjoint_profiles() {
+        let (p, q) = (
+            SparseProfile::from_str("aaaa", 2),
+            SparseProfile::from_str("bbbb", 2),
+        );
+        assert!(close(jensen_shannon_divergence(&p, &q), 1.0));
+        assert!(close(jensen_shannon_distance(&p, &q), 1.0));
+        assert!(close(hellinger_distance(&p, &q), 1.0));
+        assert_eq!(bhattacharyya_distance(&p, &q), f64::INFINITY);
+        assert!(kullback_leibler_divergence(&p, &q, 0.01).is_finite());
+    }
+
+    #[test]
+    fn on_counts_matter() {
+        // overlapping grams with different frequencies
+        let (p, q) = (
+            SparseProfile::from_str("aaaab", 2),
+            SparseProfile::from_str("aabbb", 2),
+        );
+        let jsd = jensen_shannon_divergence(&p, &q);
+        assert!(jsd > 0.0 && jsd < 1.0);
+        assert!(close(jsd, jensen_shannon_divergence(&q, &p)));
+        assert!(
+            kullback_leibler_divergence(&p, &q, 0.5) != kullback_leibler_divergence(&q, &p, 0.5)
+        );
+    }
+
+    #[test]
+    fn on_product_descriptions() {
+        let js = JensenShannon { n: 2 };
+        let h = Hellinger { n: 2 };
+        let a = "black leather wallet slim";
+        let b = "slim black leather wallet";
+        let c = "stainless steel water bottle";
+        assert!(js.similarity(a, b) > js.similarity(a, c));
+        assert!(h.similarity(a, b) > h.similarity(a, c));
+        assert_eq!(js.similarity("", ""), 1.0);
+        assert_eq!(h.distance("", "ab"), 1.0);
+    }
+}
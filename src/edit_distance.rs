@@ -0,0 +1,378 @@
+use crate::metric::{StringMetric, UnitMetric};
+use crate::ratcliff_obershelp::{opcodes, Match, Opcode};
+use std::borrow::Cow;
+use std::cmp::min;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// levenshtein_distance: minimum number of single char insertions, deletions or substitutions
+/// to turn `s1` into `s2`. higher score is less similar.
+/// [levenshtein wikipedia](https://en.wikipedia.org/wiki/Levenshtein_distance)
+///
+/// operates on chars, not bytes, so "Zürich" and "Zurich" differ by one substitution.
+pub fn levenshtein_distance(s1: &str, s2: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    levenshtein(&a, &b)
+}
+
+/// levenshtein_similarity: 1 - levenshtein_distance / max char count. higher score is more similar.
+pub fn levenshtein_similarity(s1: &str, s2: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    normalize(levenshtein(&a, &b), a.len(), b.len())
+}
+
+/// osa_distance: Optimal String Alignment, levenshtein plus transposition of two adjacent chars,
+/// with the restriction that no substring is edited more than once ("ca" -> "abc" is 3, not 2).
+/// higher score is less similar.
+/// [damerau-levenshtein wikipedia](https://en.wikipedia.org/wiki/Damerau%E2%80%93Levenshtein_distance#Optimal_string_alignment_distance)
+pub fn osa_distance(s1: &str, s2: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    osa(&a, &b)
+}
+
+/// osa_similarity: 1 - osa_distance / max char count. higher score is more similar.
+pub fn osa_similarity(s1: &str, s2: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    normalize(osa(&a, &b), a.len(), b.len())
+}
+
+/// damerau_levenshtein_distance: unrestricted Damerau-Levenshtein, levenshtein plus transposition
+/// of two adjacent chars where substrings may be edited more than once ("ca" -> "abc" is 2).
+/// higher score is less similar.
+/// [damerau-levenshtein wikipedia](https://en.wikipedia.org/wiki/Damerau%E2%80%93Levenshtein_distance#Distance_with_adjacent_transpositions)
+pub fn damerau_levenshtein_distance(s1: &str, s2: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    damerau_levenshtein(&a, &b)
+}
+
+/// damerau_levenshtein_similarity: 1 - damerau_levenshtein_distance / max char count. higher score is more similar.
+pub fn damerau_levenshtein_similarity(s1: &str, s2: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    normalize(damerau_levenshtein(&a, &b), a.len(), b.len())
+}
+
+/// normalize: edit distance `d` to [0,1] similarity by the longer length; two empty inputs are identical.
+fn normalize(d: usize, a_len: usize, b_len: usize) -> f64 {
+    let longest = a_len.max(b_len);
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - (d as f64 / longest as f64)
+}
+
+/// levenshtein over two rows of the dp matrix.
+fn levenshtein<T: Eq>(a: &[T], b: &[T]) -> usize {
+    if a.is_empty() {
+        return b.len();
+    }
+    if b.is_empty() {
+        return a.len();
+    }
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr: Vec<usize> = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let cost = if x == y { 0 } else { 1 };
+            curr[j + 1] = min(min(prev[j + 1] + 1, curr[j] + 1), prev[j] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// levenshtein_alignment: one optimal unit cost alignment of `a` and `b` as pairs of positions,
+/// `(Some(i), Some(j))` for a match or substitution, `(Some(i), None)` for a deletion from `a`
+/// and `(None, Some(j))` for an insertion from `b`. Ties prefer match/substitution, then deletion.
+/// Works on any slice: chars for a char alignment, tokens for a token alignment.
+pub fn levenshtein_alignment<T: Eq>(a: &[T], b: &[T]) -> Vec<(Option<usize>, Option<usize>)> {
+    let cols = b.len() + 1;
+    let mut d = vec![0; (a.len() + 1) * cols];
+    let idx = |i: usize, j: usize| i * cols + j;
+    for i in 0..=a.len() {
+        d[idx(i, 0)] = i;
+    }
+    for j in 0..=b.len() {
+        d[idx(0, j)] = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[idx(i, j)] = min(
+                min(d[idx(i - 1, j)] + 1, d[idx(i, j - 1)] + 1),
+                d[idx(i - 1, j - 1)] + cost,
+            );
+        }
+    }
+
+    let mut ops = Vec::with_capacity(a.len().max(b.len()));
+    let (mut i, mut j) = (a.len(), b.len());
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            if d[idx(i, j)] == d[idx(i - 1, j - 1)] + cost {
+                ops.push((Some(i - 1), Some(j - 1)));
+                i -= 1;
+                j -= 1;
+                continue;
+            }
+        }
+        if i > 0 && d[idx(i, j)] == d[idx(i - 1, j)] + 1 {
+            ops.push((Some(i - 1), None));
+            i -= 1;
+        } else {
+            ops.push((None, Some(j - 1)));
+            j -= 1;
+        }
+    }
+    ops.reverse();
+    ops
+}
+
+/// levenshtein_opcodes: `levenshtein_alignment` as a difflib style edit script; runs of matches are `Equal`,
+/// the edits between them a `Replace`, `Insert` or `Delete`, so reviewers see which ranges cost the distance.
+///
+///    let (a, b): (Vec<char>, Vec<char>) = ("P0LICY".chars().collect(), "POLICY".chars().collect());
+///    levenshtein_opcodes(&a, &b) // Equal 0..1, Replace 1..2 -> 1..2, Equal 2..6
+pub fn levenshtein_opcodes<T: Eq>(a: &[T], b: &[T]) -> Vec<Opcode> {
+    let mut blocks: Vec<Match> = Vec::new();
+    for (i, j) in levenshtein_alignment(a, b) {
+        if let (Some(i), Some(j)) = (i, j) {
+            if a[i] != b[j] {
+                continue;
+            }
+            match blocks.last_mut() {
+                Some(m) if m.a + m.size == i && m.b + m.size == j => m.size += 1,
+                _ => blocks.push(Match {
+                    a: i,
+                    b: j,
+                    size: 1,
+                }),
+            }
+        }
+    }
+    blocks.push(Match {
+        a: a.len(),
+        b: b.len(),
+        size: 0,
+    });
+    opcodes(&blocks)
+}
+
+/// osa over three rows of the dp matrix; the extra row is needed for the transposition lookback.
+fn osa<T: Eq>(a: &[T], b: &[T]) -> usize {
+    if a.is_empty() {
+        return b.len();
+    }
+    if b.is_empty() {
+        return a.len();
+    }
+    let mut prev2: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr: Vec<usize> = vec![0; b.len() + 1];
+    for i in 0..a.len() {
+        curr[0] = i + 1;
+        for j in 0..b.len() {
+            let cost = if a[i] == b[j] { 0 } else { 1 };
+            curr[j + 1] = min(min(prev[j + 1] + 1, curr[j] + 1), prev[j] + cost);
+            if i > 0 && j > 0 && a[i] == b[j - 1] && a[i - 1] == b[j] {
+                curr[j + 1] = min(curr[j + 1], prev2[j - 1] + 1);
+            }
+        }
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// damerau_levenshtein with the Lowrance-Wagner full matrix, tracking the last row each element was seen in `a`.
+fn damerau_levenshtein<T: Eq + Hash>(a: &[T], b: &[T]) -> usize {
+    if a.is_empty() {
+        return b.len();
+    }
+    if b.is_empty() {
+        return a.len();
+    }
+    let (rows, cols) = (a.len() + 2, b.len() + 2);
+    let max_dist = a.len() + b.len();
+    let mut d = vec![0; rows * cols];
+    let idx = |i: usize, j: usize| i * cols + j;
+
+    d[idx(0, 0)] = max_dist;
+    for i in 0..=a.len() {
+        d[idx(i + 1, 0)] = max_dist;
+        d[idx(i + 1, 1)] = i;
+    }
+    for j in 0..=b.len() {
+        d[idx(0, j + 1)] = max_dist;
+        d[idx(1, j + 1)] = j;
+    }
+
+    let mut last_row: HashMap<&T, usize> = HashMap::new();
+    for i in 1..=a.len() {
+        let mut last_match_col = 0;
+        for j in 1..=b.len() {
+            let k = *last_row.get(&b[j - 1]).unwrap_or(&0);
+            let l = last_match_col;
+            let cost = if a[i - 1] == b[j - 1] {
+                last_match_col = j;
+                0
+            } else {
+                1
+            };
+            let substitution = d[idx(i, j)] + cost;
+            let insertion = d[idx(i + 1, j)] + 1;
+            let deletion = d[idx(i, j + 1)] + 1;
+            let transposition = d[idx(k, l)] + (i - k - 1) + 1 + (j - l - 1);
+            d[idx(i + 1, j + 1)] = min(min(substitution, insertion), min(deletion, transposition));
+        }
+        last_row.insert(&a[i - 1], i);
+    }
+    d[idx(a.len() + 1, b.len() + 1)]
+}
+
+/// Levenshtein implements `StringMetric` with `levenshtein_similarity`; the raw score is the edit count.
+pub struct Levenshtein;
+
+/// OptimalStringAlignment implements `StringMetric` with `osa_similarity`; the raw score is the edit count.
+pub struct OptimalStringAlignment;
+
+/// DamerauLevenshtein implements `StringMetric` with `damerau_levenshtein_similarity`; the raw score is the edit count.
+pub struct DamerauLevenshtein;
+
+impl StringMetric for Levenshtein {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        levenshtein_distance(s1, s2) as f64
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        levenshtein_similarity(s1, s2)
+    }
+}
+
+impl StringMetric for OptimalStringAlignment {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        osa_distance(s1, s2) as f64
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        osa_similarity(s1, s2)
+    }
+}
+
+impl StringMetric for DamerauLevenshtein {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        damerau_levenshtein_distance(s1, s2) as f64
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        damerau_levenshtein_similarity(s1, s2)
+    }
+}
+
+impl UnitMetric for Levenshtein {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        levenshtein(a, b) as f64
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        normalize(levenshtein(a, b), a.len(), b.len())
+    }
+}
+
+impl UnitMetric for OptimalStringAlignment {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        osa(a, b) as f64
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        normalize(osa(a, b), a.len(), b.len())
+    }
+}
+
+impl UnitMetric for DamerauLevenshtein {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        damerau_levenshtein(a, b) as f64
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        normalize(damerau_levenshtein(a, b), a.len(), b.len())
+    }
+}
+
+#[allow(clippy::float_cmp)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ratcliff_obershelp::Tag;
+
+    #[test]
+    fn on_levenshtein_opcodes() {
+        let (a, b): (Vec<char>, Vec<char>) =
+            ("P0LICY".chars().collect(), "POLICY".chars().collect());
+        let ops = levenshtein_opcodes(&a, &b);
+        let tags: Vec<Tag> = ops.iter().map(|o| o.tag).collect();
+        assert_eq!(tags, vec![Tag::Equal, Tag::Replace, Tag::Equal]);
+        assert_eq!((ops[1].i1, ops[1].i2, ops[1].j1, ops[1].j2), (1, 2, 1, 2));
+
+        let (a, b): (Vec<char>, Vec<char>) = ("DATE.".chars().collect(), "ADATE".chars().collect());
+        let tags: Vec<Tag> = levenshtein_opcodes(&a, &b).iter().map(|o| o.tag).collect();
+        assert_eq!(tags, vec![Tag::Insert, Tag::Equal, Tag::Delete]);
+
+        // token granularity
+        let a = vec!["black", "leather", "wallet"];
+        let b = vec!["black", "wallet", "slim"];
+        assert_eq!(levenshtein_alignment(&a, &b).len(), 3);
+        let ops = levenshtein_opcodes(&a, &b);
+        let edits: usize = ops
+            .iter()
+            .filter(|o| o.tag != Tag::Equal)
+            .map(|o| (o.i2 - o.i1).max(o.j2 - o.j1))
+            .sum();
+        assert_eq!(edits, levenshtein(&a, &b));
+        let empty: Vec<char> = Vec::new();
+        assert!(levenshtein_opcodes(&empty, &empty).is_empty());
+    }
+
+    #[test]
+    fn on_levenshtein() {
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("", "abc"), 3);
+        assert_eq!(levenshtein_distance("abc", ""), 3);
+        assert_eq!(levenshtein_distance("ab", "ba"), 2);
+        assert_eq!(levenshtein_distance("Zürich", "Zurich"), 1);
+        assert_eq!(levenshtein_similarity("", ""), 1.0);
+        assert_eq!(levenshtein_similarity("Café", "Cafe"), 0.75);
+    }
+
+    #[test]
+    fn on_osa() {
+        assert_eq!(osa_distance("ab", "ba"), 1);
+        assert_eq!(osa_distance("ca", "abc"), 3);
+        assert_eq!(osa_distance("POLICY 1O5", "POLICY O15"), 1);
+        assert_eq!(osa_distance("kitten", "sitting"), 3);
+        assert_eq!(osa_similarity("abcd", "abdc"), 0.75);
+    }
+
+    #[test]
+    fn on_damerau_levenshtein() {
+        assert_eq!(damerau_levenshtein_distance("ab", "ba"), 1);
+        assert_eq!(damerau_levenshtein_distance("ca", "abc"), 2);
+        assert_eq!(damerau_levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(damerau_levenshtein_distance("", "ab"), 2);
+        assert_eq!(damerau_levenshtein_distance("größe", "grösse"), 2);
+        assert_eq!(damerau_levenshtein_similarity("abcd", "abdc"), 0.75);
+    }
+
+    #[test]
+    fn on_edit_distance_ordering() {
+        // dl <= osa <= levenshtein always holds
+        for &(a, b) in &[
+            ("ca", "abc"),
+            ("a cat", "an act"),
+            ("1O5S", "O1S5"),
+            ("", "x"),
+        ] {
+            let (l, o, d) = (
+                levenshtein_distance(a, b),
+                osa_distance(a, b),
+                damerau_levenshtein_distance(a, b),
+            );
+            assert!(d <= o && o <= l);
+        }
+    }
+}
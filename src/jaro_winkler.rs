@@ -1,4 +1,99 @@
+use crate::metric::{StringMetric, UnitMetric};
+use std::borrow::Cow;
 use std::cmp::{max, min};
+use std::collections::HashMap;
+
+/// JaroWinklerError for invalid `JaroWinklerConfig` parameters.
+#[derive(Debug, PartialEq)]
+pub enum JaroWinklerError {
+    /// the prefix scale is negative, or large enough with the max prefix length to push a similarity above 1.
+    PrefixScale(f64),
+    /// the boost threshold is outside [0,1].
+    BoostThreshold(f64),
+}
+/// fmt for JaroWinklerError
+impl std::fmt::Display for JaroWinklerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            JaroWinklerError::PrefixScale(p) => write!(
+                f,
+                "ERROR: prefix scale {} must be within [0, 1 / max prefix length]",
+                p
+            ),
+            JaroWinklerError::BoostThreshold(t) => {
+                write!(f, "ERROR: boost threshold {} must be within [0,1]", t)
+            }
+        }
+    }
+}
+
+/// JaroWinklerConfig holds Winkler's parameters:
+///
+/// * `prefix_scale` p: boost per common prefix char, at most `1 / max_prefix` so similarity stays within [0,1]
+/// * `max_prefix`: cap on the common prefix length l
+/// * `boost_threshold`: only strings with a jaro similarity at or above it get the prefix boost
+///
+/// The default is Winkler's p = 0.1, l <= 4 and threshold 0.7.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JaroWinklerConfig {
+    pub prefix_scale: f64,
+    pub max_prefix: usize,
+    pub boost_threshold: f64,
+}
+
+impl Default for JaroWinklerConfig {
+    fn default() -> Self {
+        JaroWinklerConfig {
+            prefix_scale: 0.1,
+            max_prefix: 4,
+            boost_threshold: 0.7,
+        }
+    }
+}
+
+impl JaroWinklerConfig {
+    /// new: a validated config.
+    pub fn new(
+        prefix_scale: f64,
+        max_prefix: usize,
+        boost_threshold: f64,
+    ) -> Result<Self, JaroWinklerError> {
+        let config = JaroWinklerConfig {
+            prefix_scale,
+            max_prefix,
+            boost_threshold,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// validate: `Err` when a parameter is out of range or NaN.
+    pub fn validate(&self) -> Result<(), JaroWinklerError> {
+        let p = self.prefix_scale;
+        if !(p >= 0.0 && p * self.max_prefix as f64 <= 1.0) {
+            return Err(JaroWinklerError::PrefixScale(p));
+        }
+        if !(0.0..=1.0).contains(&self.boost_threshold) {
+            return Err(JaroWinklerError::BoostThreshold(self.boost_threshold));
+        }
+        Ok(())
+    }
+
+    /// similarity: jaro winkler similarity of `a` and `b` under this config, assumed valid. higher score is more similar.
+    fn similarity<T: Eq>(&self, a: &[T], b: &[T]) -> f64 {
+        let sim_j = jaro_similarity_slices(a, b);
+        if sim_j < self.boost_threshold {
+            return sim_j;
+        }
+        let l = a
+            .iter()
+            .zip(b.iter())
+            .take_while(|&(x, y)| x == y)
+            .take(self.max_prefix)
+            .count() as f64;
+        sim_j + l * self.prefix_scale * (1.0 - sim_j)
+    }
+}
 
 /// jaro_winkler_distance: higher score is less similar.
 /// [jaro-winkler wikipedia](https://en.wikipedia.org/wiki/Jaro%E2%80%93Winkler_distance)
@@ -9,30 +104,45 @@ use std::cmp::{max, min};
 /// t number of transpositions
 /// l is the length of common prefix, the number of symbols at beginning before first mismatch (max is 4)
 ///
-/// jwd = 1 - (sim_j * (1 - l*p))
-/// this is more performant than the often defined jwd = 1 - sim_jwr due to less calculations in the jaro winkler similarity function.
-pub fn jaro_winkler_distance(s1: &str, s2: &str, mut p: f64) -> f64 {
-    let mut l = s1
-        .chars()
-        .zip(s2.chars())
-        .take_while(|&(s1_char, s2_char)| s1_char == s2_char)
-        .count() as f64;
-    if l > 4.0 {
-        l = 4.0;
-    };
-    if p > 0.25 {
-        p = 0.25
-    }
-    (1.0 - jaro_similarity(s1, s2)) * (1.0 - l * p)
+/// jwd = 1 - sim_jw, with the default max prefix length 4 and boost threshold 0.7 of `JaroWinklerConfig`.
+/// Returns `JaroWinklerError::PrefixScale` when p is outside [0,0.25].
+pub fn jaro_winkler_distance(s1: &str, s2: &str, p: f64) -> Result<f64, JaroWinklerError> {
+    Ok(1.0 - jaro_winkler_similarity(s1, s2, p)?)
 }
 
 /// jaro_winkler_similarity: higher score is more similar.
-/// sim_jw = 1 - jwd : jwd calculates 1-sim_jaro * (1-l*p)
+/// sim_jw = sim_j + l*p(1-sim_j) when sim_j >= 0.7, otherwise sim_j
 ///
-/// often: sim_jw = sim_j + l*p(1-sim_j);
-/// a more performant calculation: 1 - jwd : jwd calculates 1-sim_jaro * (1-l*p)
-pub fn jaro_winkler_similarity(s1: &str, s2: &str, p: f64) -> f64 {
-    1.0 - jaro_winkler_distance(s1, s2, p)
+/// see `jaro_winkler_similarity_with` for other max prefix lengths and boost thresholds.
+pub fn jaro_winkler_similarity(s1: &str, s2: &str, p: f64) -> Result<f64, JaroWinklerError> {
+    let config = JaroWinklerConfig {
+        prefix_scale: p,
+        ..JaroWinklerConfig::default()
+    };
+    jaro_winkler_similarity_with(s1, s2, &config)
+}
+
+/// jaro_winkler_similarity_with: jaro winkler similarity with all of Winkler's parameters from `config`.
+/// higher score is more similar.
+pub fn jaro_winkler_similarity_with(
+    s1: &str,
+    s2: &str,
+    config: &JaroWinklerConfig,
+) -> Result<f64, JaroWinklerError> {
+    let a: Vec<char> = s1.chars().collect();
+    let b: Vec<char> = s2.chars().collect();
+    jaro_winkler_similarity_slices(&a, &b, config)
+}
+
+/// jaro_winkler_similarity_slices: jaro_winkler_similarity_with over any element slices, the common prefix
+/// counted in elements. higher score is more similar.
+pub fn jaro_winkler_similarity_slices<T: Eq>(
+    a: &[T],
+    b: &[T],
+    config: &JaroWinklerConfig,
+) -> Result<f64, JaroWinklerError> {
+    config.validate()?;
+    Ok(config.similarity(a, b))
 }
 
 // jaro_func(A,B.m,t,l,p) = f(A,B,m,t) * (1 - l*p)
@@ -50,55 +160,61 @@ fn calculate(a: f64, b: f64, m: f64, t: f64) -> f64 {
 /// m is matching chars, the number of shared symbols
 /// t is number of needed transpositions fo shared symbols
 /// l is the length of common prefix, the number of symbols at beginning before first mismatch (max is 4)
-fn jaro_similarity(s1: &str, s2: &str) -> f64 {
+pub fn jaro_similarity(s1: &str, s2: &str) -> f64 {
     //exact strings no need to calculate
     if s1 == s2 {
         return 1.0;
     }
-    let s1_char_count = s1.chars().count();
-    let s2_char_count = s2.chars().count();
-    //zero char count, "empty" string are equidistant
-    if s1_char_count == 0 || s2_char_count == 0 {
+    let a: Vec<char> = s1.chars().collect();
+    let b: Vec<char> = s2.chars().collect();
+    jaro_similarity_slices(&a, &b)
+}
+
+/// jaro_similarity_slices: jaro_similarity over any element slices, e.g. tokens, grapheme clusters or
+/// attribute codes. higher score is more similar.
+pub fn jaro_similarity_slices<T: Eq>(a: &[T], b: &[T]) -> f64 {
+    //exact sequences no need to calculate
+    if a == b {
         return 1.0;
     }
-    // since s1 == s2 already been checked, we know these are extreme
-    if s1_char_count == 1 && s2_char_count == 1 {
+    //only one sequence is empty, nothing can match
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    // since a == b already been checked, we know these are extreme
+    if a.len() == 1 && b.len() == 1 {
         return 0.0;
     }
-    // m matching chars, t transpositions
+    // m matching elements, t transpositions
     let (mut m, mut t) = (0.0, 0.0);
-    // s1, s2 matching if they are the same and not farther than window
-    let window = (max(s1_char_count, s2_char_count) / 2) - 1;
+    // a, b matching if they are the same and not farther than window
+    let window = (max(a.len(), b.len()) / 2).saturating_sub(1);
     // index we can increment tandem with window
-    let mut s2_index: usize = 0;
-    // fill vec with 0==false for postional checks
-    let mut match_idx: Vec<u8> = Vec::with_capacity(s2_char_count as usize);
-    for _i in 0..s2_char_count {
-        match_idx.push(0)
-    }
-
-    // TODO refactor to iterator for next
-    for (i, a) in s1.chars().enumerate() {
-        //define proper min,max for window for s2 index comparisons
-        let min_limit = if i > window { max(0, i - window) } else { 0 };
-        let max_limit = min(s2_char_count - 1, i + window);
+    let mut b_index: usize = 0;
+    // false for postional checks
+    let mut matched = vec![false; b.len()];
+
+    for (i, x) in a.iter().enumerate() {
+        //define proper min,max for window for b index comparisons
+        let min_limit = i.saturating_sub(window);
+        let max_limit = min(b.len() - 1, i + window);
         // next
         if min_limit > max_limit {
             continue;
         }
 
-        for (j, b) in s2.chars().enumerate() {
+        for j in min_limit..=max_limit {
             //check for match
-            if min_limit <= j && j <= max_limit && a == b && match_idx[j] == 0 {
-                // found match, set 1==true, increment m
-                match_idx[j] = 1;
+            if !matched[j] && *x == b[j] {
+                // found match, increment m
+                matched[j] = true;
                 m += 1.0;
                 // check transpose,
-                if j < s2_index {
+                if j < b_index {
                     t += 1.0;
                 }
                 // increment index, move on
-                s2_index = j;
+                b_index = j;
                 break;
             }
         }
@@ -107,5 +223,434 @@ fn jaro_similarity(s1: &str, s2: &str) -> f64 {
     if m == 0.0 {
         return 0.0;
     }
-    calculate(s1_char_count as f64, s2_char_count as f64, m, t)
+    calculate(a.len() as f64, b.len() as f64, m, t)
+}
+
+/// jaro_distance: 1 - jaro_similarity. higher score is less similar.
+pub fn jaro_distance(s1: &str, s2: &str) -> f64 {
+    1.0 - jaro_similarity(s1, s2)
+}
+
+/// SimilarChars is a symmetric table of partial credit, within (0,1], for pairs of chars that are likely
+/// confused (look-alikes for OCR, neighbouring keys for keying errors). Used by `strcmp95_similarity`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SimilarChars {
+    credits: HashMap<(char, char), f64>,
+}
+
+impl SimilarChars {
+    /// new: an empty table.
+    pub fn new() -> Self {
+        SimilarChars::default()
+    }
+
+    /// census: the uppercase table of the Census Bureau strcmp95, crediting 0.3 of a match for each pair.
+    pub fn census() -> Self {
+        let pairs = [
+            ('A', 'E'),
+            ('A', 'I'),
+            ('A', 'O'),
+            ('A', 'U'),
+            ('B', 'V'),
+            ('E', 'I'),
+            ('E', 'O'),
+            ('E', 'U'),
+            ('I', 'O'),
+            ('I', 'U'),
+            ('O', 'U'),
+            ('I', 'Y'),
+            ('E', 'Y'),
+            ('C', 'G'),
+            ('E', 'F'),
+            ('W', 'U'),
+            ('W', 'V'),
+            ('X', 'K'),
+            ('S', 'Z'),
+            ('X', 'S'),
+            ('Q', 'C'),
+            ('U', 'V'),
+            ('M', 'N'),
+            ('L', 'I'),
+            ('Q', 'O'),
+            ('P', 'R'),
+            ('I', 'J'),
+            ('2', 'Z'),
+            ('5', 'S'),
+            ('8', 'B'),
+            ('1', 'I'),
+            ('1', 'L'),
+            ('0', 'O'),
+            ('0', 'Q'),
+            ('C', 'K'),
+            ('G', 'J'),
+            ('E', ' '),
+            ('Y', ' '),
+            ('S', ' '),
+        ];
+        let mut table = SimilarChars::new();
+        for &(a, b) in pairs.iter() {
+            table.insert(a, b, 0.3);
+        }
+        table
+    }
+
+    /// insert: credit `a` and `b` as similar, both ways.
+    pub fn insert(&mut self, a: char, b: char, credit: f64) {
+        self.credits.insert((a, b), credit);
+        self.credits.insert((b, a), credit);
+    }
+
+    /// credit: the partial credit for `a` against `b`, 0.0 when not similar.
+    pub fn credit(&self, a: char, b: char) -> f64 {
+        *self.credits.get(&(a, b)).unwrap_or(&0.0)
+    }
+}
+
+/// strcmp95_similarity: the Census Bureau strcmp95 comparator, jaro winkler with two adjustments. higher score is more similar.
+///
+/// * chars left unmatched by jaro earn `similar` credit against an unmatched char of the other string,
+///   so look-alike substitutions ("0" for "O") are not full mismatches; only when the shorter string has unmatched chars
+/// * the Winkler prefix boost (p = 0.1, l <= 4, when the score is above 0.7) does not count digits
+/// * `long_adjust`: when both strings are longer than 4 chars and at least half of the chars beyond the common prefix
+///   match, the score is raised towards 1 by the share of those extra matches
+///
+/// Leading and trailing whitespace is ignored and an empty string scores 0.0. Chars are compared as given:
+/// the original uppercases both strings first, which `SimilarChars::census` assumes.
+///
+/// References:
+///
+/// * [strcmp95.c](https://web.archive.org/web/20100227020019/http://www.census.gov/geo/msb/stand/strcmp.c)
+/// * [Winkler, String Comparator Metrics and Enhanced Decision Rules](https://files.eric.ed.gov/fulltext/ED325505.pdf)
+pub fn strcmp95_similarity(s1: &str, s2: &str, similar: &SimilarChars, long_adjust: bool) -> f64 {
+    let a: Vec<char> = s1.trim().chars().collect();
+    let b: Vec<char> = s2.trim().chars().collect();
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let (len_a, len_b) = (a.len(), b.len());
+    let minv = len_a.min(len_b);
+    let window = (len_a.max(len_b) / 2).saturating_sub(1);
+
+    let (mut a_flag, mut b_flag) = (vec![false; len_a], vec![false; len_b]);
+    let mut common = 0;
+    for (i, &c) in a.iter().enumerate() {
+        let lo = i.saturating_sub(window);
+        let hi = (i + window).min(len_b - 1);
+        for j in lo..=hi {
+            if !b_flag[j] && b[j] == c {
+                a_flag[i] = true;
+                b_flag[j] = true;
+                common += 1;
+                break;
+            }
+        }
+    }
+    if common == 0 {
+        return 0.0;
+    }
+
+    // half transpositions: matched chars in a different order
+    let mut half_transpositions = 0;
+    let mut k = 0;
+    for (i, &c) in a.iter().enumerate() {
+        if a_flag[i] {
+            while !b_flag[k] {
+                k += 1;
+            }
+            if c != b[k] {
+                half_transpositions += 1;
+            }
+            k += 1;
+        }
+    }
+    let transpositions = (half_transpositions / 2) as f64;
+
+    // partial credit between unmatched chars
+    let mut similar_credit = 0.0;
+    if minv > common {
+        let mut b_used = b_flag.clone();
+        for (i, &c) in a.iter().enumerate() {
+            if a_flag[i] {
+                continue;
+            }
+            for j in 0..len_b {
+                let credit = similar.credit(c, b[j]);
+                if !b_used[j] && credit > 0.0 {
+                    similar_credit += credit;
+                    b_used[j] = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    let (common, weighted) = (common as f64, similar_credit + common as f64);
+    let mut weight =
+        (weighted / len_a as f64 + weighted / len_b as f64 + (common - transpositions) / common)
+            / 3.0;
+
+    let mut prefix = 0;
+    if weight > 0.7 {
+        while prefix < minv.min(4) && a[prefix] == b[prefix] && !a[prefix].is_ascii_digit() {
+            prefix += 1;
+        }
+        weight += prefix as f64 * 0.1 * (1.0 - weight);
+
+        let prefix = prefix as f64;
+        if long_adjust
+            && minv > 4
+            && common > prefix + 1.0
+            && 2.0 * common >= minv as f64 + prefix
+            && !a[0].is_ascii_digit()
+        {
+            weight += (1.0 - weight) * (common - prefix - 1.0)
+                / ((len_a + len_b) as f64 - prefix * 2.0 + 2.0);
+        }
+    }
+    weight
+}
+
+/// strcmp95_distance: 1 - strcmp95_similarity. higher score is less similar.
+pub fn strcmp95_distance(s1: &str, s2: &str, similar: &SimilarChars, long_adjust: bool) -> f64 {
+    1.0 - strcmp95_similarity(s1, s2, similar, long_adjust)
+}
+
+/// Jaro implements `StringMetric` with `jaro_similarity`; the raw score is the similarity.
+pub struct Jaro;
+
+/// JaroWinkler implements `StringMetric` with `jaro_winkler_similarity_with` for a validated config;
+/// the raw score is the similarity.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct JaroWinkler {
+    config: JaroWinklerConfig,
+}
+
+impl JaroWinkler {
+    /// new: `Err` when `config` does not validate.
+    pub fn new(config: JaroWinklerConfig) -> Result<Self, JaroWinklerError> {
+        config.validate()?;
+        Ok(JaroWinkler { config })
+    }
+
+    pub fn config(&self) -> &JaroWinklerConfig {
+        &self.config
+    }
+}
+
+/// Strcmp95 implements `StringMetric` with `strcmp95_similarity`, uppercasing both strings first when `uppercase`
+/// is set as the original does; the raw score is the similarity.
+pub struct Strcmp95 {
+    pub similar: SimilarChars,
+    pub long_adjust: bool,
+    pub uppercase: bool,
+}
+
+impl Default for Strcmp95 {
+    /// default: the census table, long string adjustment and uppercasing, like the original.
+    fn default() -> Self {
+        Strcmp95 {
+            similar: SimilarChars::census(),
+            long_adjust: true,
+            uppercase: true,
+        }
+    }
+}
+
+impl StringMetric for Jaro {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        jaro_similarity(s1, s2)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        jaro_similarity(s1, s2)
+    }
+}
+
+impl StringMetric for JaroWinkler {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        self.similarity(s1, s2)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        let a: Vec<char> = s1.chars().collect();
+        let b: Vec<char> = s2.chars().collect();
+        self.config.similarity(&a, &b)
+    }
+}
+
+impl UnitMetric for Jaro {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        jaro_similarity_slices(a, b)
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        jaro_similarity_slices(a, b)
+    }
+}
+
+impl UnitMetric for JaroWinkler {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        self.config.similarity(a, b)
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        self.config.similarity(a, b)
+    }
+}
+
+impl StringMetric for Strcmp95 {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        self.similarity(s1, s2)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        if self.uppercase {
+            strcmp95_similarity(
+                &s1.to_uppercase(),
+                &s2.to_uppercase(),
+                &self.similar,
+                self.long_adjust,
+            )
+        } else {
+            strcmp95_similarity(s1, s2, &self.similar, self.long_adjust)
+        }
+    }
+}
+
+#[allow(clippy::float_cmp)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize::{AlphaNumericTokenizer, TokenizerSeq};
+
+    #[test]
+    fn on_jaro() {
+        assert!((jaro_similarity("DIXON", "DICKSONX") - 0.766_666_666_666_666_7).abs() < 1e-9);
+        assert_eq!(jaro_similarity("abc", "xyz"), 0.0);
+        assert_eq!(jaro_distance("Zürich", "Zürich"), 0.0);
+        assert_eq!(jaro_similarity("", ""), 1.0);
+        assert_eq!(jaro_similarity("", "abc"), 0.0);
+        assert_eq!(Jaro.similarity("", "abc"), 0.0);
+        assert_eq!(JaroWinkler::default().similarity("", "abc"), 0.0);
+    }
+
+    #[test]
+    fn on_jaro_winkler_config() {
+        let jw = JaroWinkler::default();
+        let jaro = jaro_similarity("DIXON", "DICKSONX");
+        // l = 2
+        assert!((jw.similarity("DIXON", "DICKSONX") - (jaro + 0.2 * (1.0 - jaro))).abs() < 1e-12);
+        let long = JaroWinkler::new(JaroWinklerConfig::new(0.1, 6, 0.7).unwrap()).unwrap();
+        assert!(long.similarity("Amsterdam", "Amsterdm") > jw.similarity("Amsterdam", "Amsterdm"));
+        // below the threshold no boost
+        let (s1, s2) = ("ABCDxyzuvw", "ABCDqrstop");
+        assert!(jaro_similarity(s1, s2) < 0.7);
+        assert_eq!(jw.similarity(s1, s2), jaro_similarity(s1, s2));
+        let always = JaroWinklerConfig::new(0.1, 4, 0.0).unwrap();
+        assert!(jaro_winkler_similarity_with(s1, s2, &always).unwrap() > jaro_similarity(s1, s2));
+    }
+
+    #[test]
+    fn on_strcmp95_census_examples() {
+        let census = SimilarChars::census();
+        // values of the textdistance port of strcmp95.c, without the long string adjustment
+        for &(a, b, expected) in &[
+            ("MARTHA", "MARHTA", 0.961_111_111_111_111_1),
+            ("DWAYNE", "DUANE", 0.873),
+            ("DIXON", "DICKSONX", 0.839_333_333_333_333_4),
+            ("TEST", "TEXT", 0.906_666_666_666_666_6),
+        ] {
+            let sim = strcmp95_similarity(a, b, &census, false);
+            assert!((sim - expected).abs() < 1e-9, "{} {} {}", a, b, sim);
+        }
+        // jaro 0.9697 boosted by a prefix of 4, then by (1 - 0.9818) * (11 - 4 - 1) / (22 - 8 + 2)
+        let sim = strcmp95_similarity("SHACKLEFORD", "SHACKELFORD", &census, true);
+        assert!((sim - 0.988_636_363_636_363_6).abs() < 1e-9);
+        assert!(sim > strcmp95_similarity("SHACKLEFORD", "SHACKELFORD", &census, false));
+    }
+
+    #[test]
+    fn on_strcmp95_similar_chars() {
+        let census = SimilarChars::census();
+        let none = SimilarChars::new();
+        // an OCR look-alike earns partial credit
+        assert!(
+            strcmp95_similarity("P0LICY", "POLICY", &census, false)
+                > strcmp95_similarity("P0LICY", "POLICY", &none, false)
+        );
+        let mut ocr = SimilarChars::new();
+        ocr.insert('5', 'S', 0.5);
+        assert_eq!(ocr.credit('S', '5'), 0.5);
+        assert!(
+            strcmp95_similarity("CLAIM5", "CLAIMS", &ocr, false)
+                > strcmp95_similarity("CLAIM5", "CLAIMS", &none, false)
+        );
+        assert_eq!(strcmp95_similarity("  ", "ABC", &census, true), 0.0);
+        assert_eq!(strcmp95_distance(" ABC ", "ABC", &census, true), 0.0);
+        let m = Strcmp95::default();
+        assert_eq!(
+            m.similarity("marhta", "MARTHA"),
+            m.similarity("MARHTA", "MARTHA")
+        );
+    }
+
+    #[test]
+    fn on_jaro_winkler_validation() {
+        assert_eq!(
+            jaro_winkler_similarity("a", "b", 0.3),
+            Err(JaroWinklerError::PrefixScale(0.3))
+        );
+        assert!(jaro_winkler_distance("a", "b", -0.1).is_err());
+        assert!(JaroWinklerConfig::new(0.2, 6, 0.7).is_err());
+        assert!(JaroWinklerConfig::new(0.5, 2, 0.7).is_ok());
+        assert_eq!(
+            JaroWinklerConfig::new(0.1, 4, 1.5),
+            Err(JaroWinklerError::BoostThreshold(1.5))
+        );
+        assert!(JaroWinklerConfig::new(f64::NAN, 4, 0.7).is_err());
+        let bad = JaroWinklerConfig {
+            prefix_scale: 0.3,
+            ..JaroWinklerConfig::default()
+        };
+        assert!(JaroWinkler::new(bad).is_err());
+        assert_eq!(
+            format!("{}", JaroWinklerError::BoostThreshold(2.0)),
+            "ERROR: boost threshold 2 must be within [0,1]"
+        );
+    }
+
+    #[test]
+    fn on_jaro_slices() {
+        for (s1, s2) in [
+            ("DIXON", "DICKSONX"),
+            ("MARTHA", "MARHTA"),
+            ("Zürich", "Zurich"),
+        ]
+        .iter()
+        {
+            let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+            assert_eq!(jaro_similarity_slices(&a, &b), jaro_similarity(s1, s2));
+        }
+        // product attribute codes, one transposition
+        let (a, b) = ([101u32, 205, 330, 412], [101u32, 330, 205, 412]);
+        assert!((jaro_similarity_slices(&a, &b) - 11.0 / 12.0).abs() < 1e-9);
+        assert_eq!(jaro_similarity_slices::<u32>(&[], &[]), 1.0);
+        assert_eq!(jaro_similarity_slices(&[], &a), 0.0);
+        assert_eq!(jaro_similarity_slices(&b, &[]), 0.0);
+
+        let an = AlphaNumericTokenizer;
+        let (t1, t2) = (
+            an.sequencer("Apple iPhone 13 Pro Max"),
+            an.sequencer("apple iphone 13 pro"),
+        );
+        assert!((jaro_similarity_slices(&t1, &t2) - 2.8 / 3.0).abs() < 1e-9);
+        let jw = jaro_winkler_similarity_slices(&t1, &t2, &JaroWinklerConfig::default()).unwrap();
+        assert!((jw - 0.96).abs() < 1e-9);
+        assert_eq!(
+            jaro_winkler_similarity_slices(
+                &t1,
+                &t2,
+                &JaroWinklerConfig {
+                    prefix_scale: 0.3,
+                    ..JaroWinklerConfig::default()
+                }
+            ),
+            Err(JaroWinklerError::PrefixScale(0.3))
+        );
+    }
 }
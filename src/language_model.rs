@@ -0,0 +1,648 @@
+use crate::tfidf::TermExtractor;
+use std::collections::{HashMap, HashSet};
+
+/// START pads the beginning of every sentence so the first units have a full context.
+pub const START: &str = "<s>";
+/// END closes every sentence so the model also scores how strings end.
+pub const END: &str = "</s>";
+
+/// counts above this are trusted as is by Good-Turing; see Katz 1987.
+const GOOD_TURING_MAX: usize = 5;
+
+/// Smoothing selects how `NGramModel` estimates `P(unit | history)`.
+/// V is the vocabulary size plus one slot for units never seen in training.
+///
+/// References:
+///
+/// * [Chen & Goodman, An Empirical Study of Smoothing Techniques for Language Modeling](https://dash.harvard.edu/bitstream/handle/1/25104739/tr-10-98.pdf)
+/// * [Brants et al., Large Language Models in Machine Translation](https://www.aclweb.org/anthology/D07-1090.pdf) (stupid backoff)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Smoothing {
+    /// maximum likelihood, c(h w) / c(h); unseen n-grams get 0.
+    Mle,
+    /// add-one, (c(h w) + 1) / (c(h) + V).
+    Laplace,
+    /// add-k, (c(h w) + k) / (c(h) + kV), k > 0.
+    AddK(f64),
+    /// Katz backoff with Good-Turing discounted counts c* = (c + 1) N(c+1) / N(c) for c < 5,
+    /// the discounted mass going to the next lower order.
+    GoodTuring,
+    /// interpolated Witten-Bell, weighting the lower order by the number of distinct units seen after the history.
+    WittenBell,
+    /// stupid backoff with factor alpha within (0,1] (0.4 in Brants et al.), down to an add-one unigram.
+    /// Scores are not normalized, so its perplexity is only comparable between stupid backoff models.
+    StupidBackoff(f64),
+    /// interpolated Kneser-Ney with absolute discount d within (0,1) (0.75 is typical), lower orders use
+    /// continuation counts.
+    KneserNey(f64),
+}
+
+/// SmoothingError for `Smoothing` parameters that would give NaN, negative or all zero estimates.
+#[derive(Debug, PartialEq)]
+pub enum SmoothingError {
+    /// k is not a finite number > 0.
+    AddK(f64),
+    /// alpha is outside (0,1].
+    StupidBackoff(f64),
+    /// the discount is outside (0,1).
+    KneserNey(f64),
+}
+/// fmt for SmoothingError
+impl std::fmt::Display for SmoothingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            SmoothingError::AddK(k) => write!(f, "ERROR: add-k {} must be finite and > 0", k),
+            SmoothingError::StupidBackoff(alpha) => {
+                write!(
+                    f,
+                    "ERROR: stupid backoff alpha {} must be within (0,1]",
+                    alpha
+                )
+            }
+            SmoothingError::KneserNey(d) => {
+                write!(f, "ERROR: Kneser-Ney discount {} must be within (0,1)", d)
+            }
+        }
+    }
+}
+
+impl Smoothing {
+    /// validate: `Err` when a parameter is out of range or NaN.
+    pub fn validate(&self) -> Result<(), SmoothingError> {
+        match *self {
+            Smoothing::AddK(k) if !(k > 0.0 && k.is_finite()) => Err(SmoothingError::AddK(k)),
+            Smoothing::StupidBackoff(alpha) if !(alpha > 0.0 && alpha <= 1.0) => {
+                Err(SmoothingError::StupidBackoff(alpha))
+            }
+            Smoothing::KneserNey(d) if !(d > 0.0 && d < 1.0) => Err(SmoothingError::KneserNey(d)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// NGramModel is an n-gram language model of order `order` over the units produced by `extractor`:
+/// words with any `TokenizerSeq` (e.g. `AlphaNumericTokenizer`), or chars with `CharGrams { n: 1 }`.
+/// Each string is a sentence padded with `order - 1` `START` units and one `END` unit.
+///
+/// Probabilities are estimated with `smoothing`, maximum likelihood `P(w | h) = c(h w) / c(h)` by default,
+/// for the `order - 1` units of history `h`. Scoring a string with `log_likelihood` or `perplexity` tells
+/// how plausible it is for the kind of text the model was trained on, e.g. OCR output for a given form field.
+///
+/// References:
+///
+/// * [Jurafsky & Martin, N-gram Language Models](https://web.stanford.edu/~jurafsky/slp3/3.pdf)
+pub struct NGramModel<E: TermExtractor> {
+    pub order: usize,
+    pub extractor: E,
+    /// set with `with_smoothing`, which validates it.
+    pub smoothing: Smoothing,
+    /// counts[k][history][unit]: occurrences of `unit` after the k-unit `history`.
+    pub counts: Vec<HashMap<Vec<String>, HashMap<String, usize>>>,
+    /// context_counts[k][history]: times the k-unit `history` was followed by a unit; context_counts[0][[]] is every predicted unit.
+    pub context_counts: Vec<HashMap<Vec<String>, usize>>,
+    /// every unit seen, including `END` but not `START`.
+    pub vocabulary: HashSet<String>,
+    pub sentences: usize,
+    /// count_of_counts[k][c]: distinct (k + 1)-grams seen exactly c times, for Good-Turing.
+    count_of_counts: Vec<HashMap<usize, usize>>,
+    /// continuations[k][history][unit]: distinct units seen before `history unit`, for Kneser-Ney.
+    continuations: Vec<HashMap<Vec<String>, HashMap<String, usize>>>,
+    /// continuation_totals[k][history]: continuations[k][history] summed over units.
+    continuation_totals: Vec<HashMap<Vec<String>, usize>>,
+}
+
+impl<E: TermExtractor> NGramModel<E> {
+    /// new: an empty maximum likelihood model of `order` (at least 1); add sentences with `add_sentence`.
+    pub fn new(order: usize, extractor: E) -> Self {
+        let order = order.max(1);
+        NGramModel {
+            order,
+            extractor,
+            smoothing: Smoothing::Mle,
+            counts: vec![HashMap::new(); order],
+            context_counts: vec![HashMap::new(); order],
+            vocabulary: HashSet::new(),
+            sentences: 0,
+            count_of_counts: vec![HashMap::new(); order],
+            continuations: vec![HashMap::new(); order],
+            continuation_totals: vec![HashMap::new(); order],
+        }
+    }
+
+    /// fit: a maximum likelihood model of `order` trained on every sentence in `corpus`.
+    pub fn fit<'a, I>(order: usize, extractor: E, corpus: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut model = NGramModel::new(order, extractor);
+        for sentence in corpus {
+            model.add_sentence(sentence);
+        }
+        model
+    }
+
+    /// with_smoothing: the same model estimating probabilities with `smoothing`.
+    /// Returns the `SmoothingError` of `Smoothing::validate` when a parameter is out of range.
+    pub fn with_smoothing(mut self, smoothing: Smoothing) -> Result<Self, SmoothingError> {
+        smoothing.validate()?;
+        self.smoothing = smoothing;
+        Ok(self)
+    }
+
+    /// units: `sentence` split by the extractor and padded with `START`/`END`.
+    pub fn units(&self, sentence: &str) -> Vec<String> {
+        let mut units: Vec<String> = vec![START.to_string(); self.order - 1];
+        units.extend(self.extractor.terms(sentence));
+        units.push(END.to_string());
+        units
+    }
+
+    /// add_sentence: counts every k-gram, k in 1..=order, ending on each unit of `sentence`.
+    pub fn add_sentence(&mut self, sentence: &str) {
+        let units = self.units(sentence);
+        for i in self.order - 1..units.len() {
+            self.vocabulary.insert(units[i].clone());
+            for k in 1..=self.order {
+                let gram = &units[i + 1 - k..=i];
+                let (history, unit) = (&gram[..k - 1], &gram[k - 1]);
+                let c = self.counts[k - 1]
+                    .entry(history.to_vec())
+                    .or_default()
+                    .entry(unit.clone())
+                    .or_insert(0);
+                *c += 1;
+                let c = *c;
+                *self.context_counts[k - 1]
+                    .entry(history.to_vec())
+                    .or_insert(0) += 1;
+
+                let n_c = &mut self.count_of_counts[k - 1];
+                *n_c.entry(c).or_insert(0) += 1;
+                if c > 1 {
+                    *n_c.entry(c - 1).or_insert(0) -= 1;
+                }
+                // a new k-gram type is a new left continuation of its (k - 1) suffix
+                if c == 1 && k > 1 {
+                    let suffix = &history[1..];
+                    *self.continuations[k - 2]
+                        .entry(suffix.to_vec())
+                        .or_default()
+                        .entry(unit.clone())
+                        .or_insert(0) += 1;
+                    *self.continuation_totals[k - 2]
+                        .entry(suffix.to_vec())
+                        .or_insert(0) += 1;
+                }
+            }
+        }
+        self.sentences += 1;
+    }
+
+    /// count: occurrences of `gram`, 0 when unseen or longer than `order`.
+    pub fn count(&self, gram: &[String]) -> usize {
+        if gram.is_empty() || gram.len() > self.order {
+            return 0;
+        }
+        let (history, unit) = gram.split_at(gram.len() - 1);
+        self.follower_count(history, &unit[0])
+    }
+
+    /// context_count: times `history` was followed by any unit.
+    pub fn context_count(&self, history: &[String]) -> usize {
+        if history.len() >= self.order {
+            return 0;
+        }
+        *self.context_counts[history.len()]
+            .get(history)
+            .unwrap_or(&0)
+    }
+
+    /// vocabulary_size: V, the distinct units seen plus one slot for unseen units.
+    pub fn vocabulary_size(&self) -> usize {
+        self.vocabulary.len() + 1
+    }
+
+    fn follower_count(&self, history: &[String], unit: &str) -> usize {
+        self.counts[history.len()]
+            .get(history)
+            .and_then(|f| f.get(unit))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// follower_types: distinct units seen after `history`, N1+(h •).
+    fn follower_types(&self, history: &[String]) -> usize {
+        self.counts[history.len()]
+            .get(history)
+            .map_or(0, |f| f.len())
+    }
+
+    /// history: the last `order - 1` units of `context`.
+    fn history<'c>(&self, context: &'c [String]) -> &'c [String] {
+        &context[context.len().saturating_sub(self.order - 1)..]
+    }
+
+    /// probability: `P(unit | context)` estimated with `smoothing`; only the last `order - 1` units of `context` are used.
+    pub fn probability(&self, context: &[&str], unit: &str) -> f64 {
+        let context: Vec<String> = context.iter().map(|s| s.to_string()).collect();
+        self.estimate(self.history(&context), unit, self.smoothing)
+    }
+
+    fn estimate(&self, history: &[String], unit: &str, smoothing: Smoothing) -> f64 {
+        match smoothing {
+            Smoothing::Mle => self.mle(history, unit),
+            Smoothing::Laplace => self.add_k(history, unit, 1.0),
+            Smoothing::AddK(k) => self.add_k(history, unit, k),
+            Smoothing::GoodTuring => self.katz(history, unit),
+            Smoothing::WittenBell => self.witten_bell(history, unit),
+            Smoothing::StupidBackoff(alpha) => self.stupid_backoff(history, unit, alpha),
+            Smoothing::KneserNey(d) => self.kneser_ney(history, unit, d, true),
+        }
+    }
+
+    fn mle(&self, history: &[String], unit: &str) -> f64 {
+        let total = self.context_count(history);
+        if total == 0 {
+            return 0.0;
+        }
+        self.follower_count(history, unit) as f64 / total as f64
+    }
+
+    fn add_k(&self, history: &[String], unit: &str, k: f64) -> f64 {
+        let v = self.vocabulary_size() as f64;
+        (self.follower_count(history, unit) as f64 + k)
+            / (self.context_count(history) as f64 + k * v)
+    }
+
+    /// good_turing_discount: c* / c for a (k + 1)-gram seen c times, 1.0 when c is large or N(c + 1) is 0.
+    fn good_turing_discount(&self, k: usize, c: usize) -> f64 {
+        if c == 0 || c >= GOOD_TURING_MAX {
+            return 1.0;
+        }
+        let n_c = *self.count_of_counts[k].get(&c).unwrap_or(&0) as f64;
+        let n_c1 = *self.count_of_counts[k].get(&(c + 1)).unwrap_or(&0) as f64;
+        if n_c == 0.0 || n_c1 == 0.0 {
+            return 1.0;
+        }
+        ((c + 1) as f64 * n_c1 / n_c / c as f64).min(1.0)
+    }
+
+    fn katz(&self, history: &[String], unit: &str) -> f64 {
+        let k = history.len();
+        let c = self.follower_count(history, unit);
+        if history.is_empty() {
+            let n = self.context_count(history) as f64;
+            if n == 0.0 {
+                return 1.0 / self.vocabulary_size() as f64;
+            }
+            if c > 0 {
+                return self.good_turing_discount(0, c) * c as f64 / n;
+            }
+            // the discounted mass goes to the single unseen slot
+            let seen: f64 = self.counts[0].get(history).map_or(0.0, |f| {
+                f.values()
+                    .map(|&c| self.good_turing_discount(0, c) * c as f64)
+                    .sum()
+            });
+            return ((n - seen) / n).max(0.0);
+        }
+
+        let total = self.context_count(history) as f64;
+        if total == 0.0 {
+            return self.katz(&history[1..], unit);
+        }
+        if c > 0 {
+            return self.good_turing_discount(k, c) * c as f64 / total;
+        }
+        let (mut seen, mut seen_lower) = (0.0, 0.0);
+        if let Some(followers) = self.counts[k].get(history) {
+            for (w, &c) in followers {
+                seen += self.good_turing_discount(k, c) * c as f64 / total;
+                seen_lower += self.katz(&history[1..], w);
+            }
+        }
+        let (left, left_lower) = (1.0 - seen, 1.0 - seen_lower);
+        if left <= 0.0 || left_lower <= 0.0 {
+            return 0.0;
+        }
+        left / left_lower * self.katz(&history[1..], unit)
+    }
+
+    fn witten_bell(&self, history: &[String], unit: &str) -> f64 {
+        let lower = if history.is_empty() {
+            1.0 / self.vocabulary_size() as f64
+        } else {
+            self.witten_bell(&history[1..], unit)
+        };
+        let total = self.context_count(history) as f64;
+        if total == 0.0 {
+            return lower;
+        }
+        let types = self.follower_types(history) as f64;
+        (self.follower_count(history, unit) as f64 + types * lower) / (total + types)
+    }
+
+    fn stupid_backoff(&self, history: &[String], unit: &str, alpha: f64) -> f64 {
+        if history.is_empty() {
+            return self.add_k(history, unit, 1.0);
+        }
+        let c = self.follower_count(history, unit);
+        if c > 0 {
+            return c as f64 / self.context_count(history) as f64;
+        }
+        alpha * self.stupid_backoff(&history[1..], unit, alpha)
+    }
+
+    /// kneser_ney: raw counts at the highest order (`top`), continuation counts below it.
+    fn kneser_ney(&self, history: &[String], unit: &str, d: f64, top: bool) -> f64 {
+        let lower = if history.is_empty() {
+            1.0 / self.vocabulary_size() as f64
+        } else {
+            self.kneser_ney(&history[1..], unit, d, false)
+        };
+        let (c, total) = if top {
+            (
+                self.follower_count(history, unit),
+                self.context_count(history),
+            )
+        } else {
+            (
+                self.continuations[history.len()]
+                    .get(history)
+                    .and_then(|f| f.get(unit))
+                    .cloned()
+                    .unwrap_or(0),
+                *self.continuation_totals[history.len()]
+                    .get(history)
+                    .unwrap_or(&0),
+            )
+        };
+        if total == 0 {
+            return lower;
+        }
+        let types = self.follower_types(history) as f64;
+        ((c as f64 - d).max(0.0) + d * types * lower) / total as f64
+    }
+
+    fn sentence_log_likelihood(&self, sentence: &str, smoothing: Smoothing) -> (f64, usize) {
+        let units = self.units(sentence);
+        let ll = (self.order - 1..units.len())
+            .map(|i| {
+                self.estimate(self.history(&units[..i]), &units[i], smoothing)
+                    .ln()
+            })
+            .sum();
+        (ll, units.len() - (self.order - 1))
+    }
+
+    /// log_likelihood: sum of ln P over every unit of `sentence` and its `END`;
+    /// negative infinity when any estimate is 0. higher score is more plausible.
+    pub fn log_likelihood(&self, sentence: &str) -> f64 {
+        self.sentence_log_likelihood(sentence, self.smoothing).0
+    }
+
+    /// perplexity: exp(-log_likelihood / predicted units), the geometric mean inverse probability per unit,
+    /// comparable across strings of different lengths. lower score is more plausible.
+    pub fn perplexity(&self, sentence: &str) -> f64 {
+        let (ll, predicted) = self.sentence_log_likelihood(sentence, self.smoothing);
+        (-ll / predicted as f64).exp()
+    }
+
+    /// heldout_perplexity: perplexity of the whole `heldout` corpus under `smoothing`,
+    /// exp(-sum of log likelihoods / sum of predicted units). lower score is a better fit.
+    /// Returns the `SmoothingError` of `Smoothing::validate` when a parameter is out of range.
+    pub fn heldout_perplexity<'a, I>(
+        &self,
+        heldout: I,
+        smoothing: Smoothing,
+    ) -> Result<f64, SmoothingError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        smoothing.validate()?;
+        let (mut ll, mut predicted) = (0.0, 0);
+        for sentence in heldout {
+            let (l, p) = self.sentence_log_likelihood(sentence, smoothing);
+            ll += l;
+            predicted += p;
+        }
+        if predicted == 0 {
+            return Ok(1.0);
+        }
+        Ok((-ll / predicted as f64).exp())
+    }
+
+    /// rank_smoothing: `heldout_perplexity` for every candidate, best (lowest) first.
+    /// Returns the `SmoothingError` of the first candidate with a parameter out of range.
+    pub fn rank_smoothing(
+        &self,
+        heldout: &[&str],
+        candidates: &[Smoothing],
+    ) -> Result<Vec<(Smoothing, f64)>, SmoothingError> {
+        let mut ranked: Vec<(Smoothing, f64)> = candidates
+            .iter()
+            .map(|&s| Ok((s, self.heldout_perplexity(heldout.iter().cloned(), s)?)))
+            .collect::<Result<_, SmoothingError>>()?;
+        ranked.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Greater));
+        Ok(ranked)
+    }
+}
+
+#[allow(clippy::float_cmp)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tfidf::CharGrams;
+    use crate::tokenize::AlphaNumericTokenizer;
+
+    fn sam() -> Vec<&'static str> {
+        vec!["I am Sam", "Sam I am", "I do not like green eggs and ham"]
+    }
+
+    fn all_smoothing() -> Vec<Smoothing> {
+        vec![
+            Smoothing::Laplace,
+            Smoothing::AddK(0.1),
+            Smoothing::GoodTuring,
+            Smoothing::WittenBell,
+            Smoothing::KneserNey(0.75),
+        ]
+    }
+
+    #[test]
+    fn on_word_bigram_mle() {
+        let lm = NGramModel::fit(2, AlphaNumericTokenizer, sam());
+        assert_eq!(lm.sentences, 3);
+        assert_eq!(lm.probability(&[START], "i"), 2.0 / 3.0);
+        assert_eq!(lm.probability(&[START], "sam"), 1.0 / 3.0);
+        assert_eq!(lm.probability(&["i"], "am"), 2.0 / 3.0);
+        assert_eq!(lm.probability(&["am"], END), 1.0 / 2.0);
+        assert_eq!(lm.probability(&["sam"], END), 1.0 / 2.0);
+        assert_eq!(lm.probability(&["i"], "do"), 1.0 / 3.0);
+        // only the last unit of the context is used by a bigram model
+        assert_eq!(lm.probability(&["green", "i"], "do"), 1.0 / 3.0);
+        assert_eq!(lm.probability(&["unseen"], "am"), 0.0);
+    }
+
+    #[test]
+    fn on_word_log_likelihood() {
+        let lm = NGramModel::fit(2, AlphaNumericTokenizer, sam());
+        // P(i|<s>) P(am|i) P(sam|am) P(</s>|sam)
+        let expected = (2.0f64 / 3.0 * 2.0 / 3.0 * 1.0 / 2.0 * 1.0 / 2.0).ln();
+        assert!((lm.log_likelihood("I am Sam") - expected).abs() < 1e-12);
+        assert!((lm.perplexity("I am Sam") - (-expected / 4.0).exp()).abs() < 1e-12);
+        assert_eq!(lm.log_likelihood("Sam likes ham"), f64::NEG_INFINITY);
+        assert_eq!(lm.perplexity("Sam likes ham"), f64::INFINITY);
+    }
+
+    #[test]
+    fn on_unigram_model() {
+        let lm = NGramModel::fit(1, AlphaNumericTokenizer, sam());
+        // 14 words + 3 ends
+        assert_eq!(lm.context_count(&[]), 17);
+        assert_eq!(lm.probability(&[], "sam"), 2.0 / 17.0);
+        assert_eq!(lm.probability(&["anything"], "sam"), 2.0 / 17.0);
+    }
+
+    #[test]
+    fn on_char_model_plausibility() {
+        let policies = vec!["POL-1024", "POL-2048", "POL-4096", "POL-8192", "POL-1240"];
+        let lm = NGramModel::fit(3, CharGrams { n: 1 }, policies);
+        assert_eq!(lm.units("PO"), vec![START, START, "P", "O", END]);
+        let plausible = lm.perplexity("POL-1024");
+        assert!(plausible.is_finite() && plausible >= 1.0);
+        assert!(lm.perplexity("P0L-1024") > plausible);
+        assert!(lm.perplexity("Zürich") > plausible);
+    }
+
+    #[test]
+    fn on_laplace() {
+        let lm = NGramModel::fit(2, AlphaNumericTokenizer, sam())
+            .with_smoothing(Smoothing::Laplace)
+            .unwrap();
+        // 10 distinct words + </s> + unseen slot
+        assert_eq!(lm.vocabulary_size(), 12);
+        assert_eq!(lm.probability(&["i"], "am"), 3.0 / 15.0);
+        assert_eq!(lm.probability(&["i"], "ham"), 1.0 / 15.0);
+        assert_eq!(lm.probability(&["unseen"], "ham"), 1.0 / 12.0);
+    }
+
+    #[test]
+    fn on_smoothing_is_a_distribution() {
+        for order in 1..=3 {
+            let lm = NGramModel::fit(order, AlphaNumericTokenizer, sam());
+            let mut units: Vec<&str> = lm.vocabulary.iter().map(|s| s.as_str()).collect();
+            units.push("unseen");
+            for &s in &all_smoothing() {
+                for context in &[
+                    vec![START, START],
+                    vec!["sam", "i"],
+                    vec!["green", "eggs"],
+                    vec!["x", "y"],
+                ] {
+                    let lm = NGramModel::fit(order, AlphaNumericTokenizer, sam())
+                        .with_smoothing(s)
+                        .unwrap();
+                    let total: f64 = units.iter().map(|u| lm.probability(context, u)).sum();
+                    assert!(
+                        (total - 1.0).abs() < 1e-9,
+                        "{:?} order {} {:?}: {}",
+                        s,
+                        order,
+                        context,
+                        total
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn on_smoothing_unseen_nonzero() {
+        let lm = NGramModel::fit(3, AlphaNumericTokenizer, sam());
+        for &s in all_smoothing()
+            .iter()
+            .chain([Smoothing::StupidBackoff(0.4)].iter())
+        {
+            let lm = NGramModel::fit(3, AlphaNumericTokenizer, sam())
+                .with_smoothing(s)
+                .unwrap();
+            assert!(lm.probability(&["green", "eggs"], "sam") > 0.0, "{:?}", s);
+            assert!(lm.perplexity("Sam likes green ham").is_finite(), "{:?}", s);
+        }
+        assert_eq!(lm.probability(&["green", "eggs"], "sam"), 0.0);
+    }
+
+    #[test]
+    fn on_stupid_backoff() {
+        let lm = NGramModel::fit(2, AlphaNumericTokenizer, sam())
+            .with_smoothing(Smoothing::StupidBackoff(0.4))
+            .unwrap();
+        assert_eq!(lm.probability(&["i"], "am"), 2.0 / 3.0);
+        // 0.4 * add-one unigram (2 + 1) / (17 + 12)
+        assert!((lm.probability(&["green"], "sam") - 0.4 * 3.0 / 29.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn on_rank_smoothing() {
+        let train = vec![
+            "POL-1024", "POL-2048", "POL-4096", "POL-8192", "POL-1240", "POL-2210", "POL-3301",
+            "POL-4410", "POL-5512", "POL-6620",
+        ];
+        let heldout = vec!["POL-7730", "POL-1234", "POL-9021"];
+        let lm = NGramModel::fit(3, CharGrams { n: 1 }, train);
+        let mut candidates = all_smoothing();
+        candidates.push(Smoothing::Mle);
+        let ranked = lm.rank_smoothing(&heldout, &candidates).unwrap();
+        assert_eq!(ranked.len(), candidates.len());
+        assert_eq!(ranked.last().unwrap().0, Smoothing::Mle);
+        assert_eq!(ranked.last().unwrap().1, f64::INFINITY);
+        for w in ranked.windows(2) {
+            assert!(w[0].1 <= w[1].1);
+        }
+        for &(_, pp) in &ranked[..ranked.len() - 1] {
+            assert!(pp.is_finite() && pp >= 1.0);
+        }
+        assert_eq!(
+            lm.heldout_perplexity(Vec::new(), Smoothing::Laplace),
+            Ok(1.0)
+        );
+        assert_eq!(
+            lm.rank_smoothing(&heldout, &[Smoothing::Laplace, Smoothing::KneserNey(1.5)]),
+            Err(SmoothingError::KneserNey(1.5))
+        );
+    }
+
+    #[test]
+    fn on_smoothing_validation() {
+        for &s in &all_smoothing() {
+            assert_eq!(s.validate(), Ok(()));
+        }
+        assert_eq!(Smoothing::Mle.validate(), Ok(()));
+        assert_eq!(Smoothing::StupidBackoff(1.0).validate(), Ok(()));
+        let lm = || NGramModel::fit(2, AlphaNumericTokenizer, sam());
+        for &k in &[0.0, -1.0, f64::INFINITY] {
+            assert_eq!(
+                lm().with_smoothing(Smoothing::AddK(k)).err(),
+                Some(SmoothingError::AddK(k))
+            );
+        }
+        for &alpha in &[0.0, -0.4, 1.5] {
+            assert_eq!(
+                lm().with_smoothing(Smoothing::StupidBackoff(alpha)).err(),
+                Some(SmoothingError::StupidBackoff(alpha))
+            );
+        }
+        for &d in &[0.0, 1.0, -0.75] {
+            assert_eq!(
+                lm().with_smoothing(Smoothing::KneserNey(d)).err(),
+                Some(SmoothingError::KneserNey(d))
+            );
+        }
+        assert!(Smoothing::AddK(f64::NAN).validate().is_err());
+        assert_eq!(
+            lm().heldout_perplexity(sam(), Smoothing::AddK(0.0)),
+            Err(SmoothingError::AddK(0.0))
+        );
+    }
+}
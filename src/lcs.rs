@@ -0,0 +1,216 @@
+use crate::metric::{StringMetric, UnitMetric};
+use std::borrow::Cow;
+
+/*
+    Longest common subsequence (elements in order, gaps allowed) and longest common substring
+    (contiguous run) by dynamic programming over two rows, O(n * m) time and O(m) space,
+    over chars or any token slice.
+
+    References:
+
+    * [Wikipedia Longest common subsequence](https://en.wikipedia.org/wiki/Longest_common_subsequence_problem)
+    * [Wikipedia Longest common substring](https://en.wikipedia.org/wiki/Longest_common_substring_problem)
+*/
+
+/// CommonSubstring is the length of the longest common substring of `a` and `b` and every `(i, j)`
+/// with `a[i..i + len] == b[j..j + len]`, in increasing order of `i` then `j`. `positions` is empty when `len` is 0.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommonSubstring {
+    pub len: usize,
+    pub positions: Vec<(usize, usize)>,
+}
+
+/// lcs_subsequence_len: length of the longest common subsequence of `a` and `b`.
+pub fn lcs_subsequence_len<T: Eq>(a: &[T], b: &[T]) -> usize {
+    let mut prev: Vec<usize> = vec![0; b.len() + 1];
+    let mut curr: Vec<usize> = vec![0; b.len() + 1];
+    for x in a {
+        for (j, y) in b.iter().enumerate() {
+            curr[j + 1] = if x == y {
+                prev[j] + 1
+            } else {
+                prev[j + 1].max(curr[j])
+            };
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// longest_common_substring: the longest common substring of `a` and `b` with all its occurrences.
+pub fn longest_common_substring<T: Eq>(a: &[T], b: &[T]) -> CommonSubstring {
+    // curr[j + 1]: length of the common run ending at a[i] and b[j]
+    let mut prev: Vec<usize> = vec![0; b.len() + 1];
+    let mut curr: Vec<usize> = vec![0; b.len() + 1];
+    let mut best = CommonSubstring {
+        len: 0,
+        positions: Vec::new(),
+    };
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            curr[j + 1] = if x == y { prev[j] + 1 } else { 0 };
+            let run = curr[j + 1];
+            if run > 0 && run >= best.len {
+                if run > best.len {
+                    best.len = run;
+                    best.positions.clear();
+                }
+                best.positions.push((i + 1 - run, j + 1 - run));
+            }
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    best.positions.sort_unstable();
+    best
+}
+
+/// lcs_subsequence_distance: insertions and deletions needed to turn `s1` into `s2`, `|s1| + |s2| - 2 * lcs`,
+/// over chars. higher score is less similar.
+pub fn lcs_subsequence_distance(s1: &str, s2: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    a.len() + b.len() - 2 * lcs_subsequence_len(&a, &b)
+}
+
+/// lcs_subsequence_similarity: `2 * lcs / (|s1| + |s2|)` over chars, within [0,1]; two empty strings are identical.
+/// higher score is more similar.
+pub fn lcs_subsequence_similarity(s1: &str, s2: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    subsequence_similarity(&a, &b)
+}
+
+/// subsequence_similarity: `2 * lcs / (|a| + |b|)` over elements, two empty slices are identical.
+fn subsequence_similarity<T: Eq>(a: &[T], b: &[T]) -> f64 {
+    let total = a.len() + b.len();
+    if total == 0 {
+        return 1.0;
+    }
+    2.0 * lcs_subsequence_len(a, b) as f64 / total as f64
+}
+
+/// lcs_substring_similarity: longest common substring length over the longer length, over chars, within [0,1];
+/// two empty strings are identical. higher score is more similar.
+pub fn lcs_substring_similarity(s1: &str, s2: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    substring_similarity(&a, &b)
+}
+
+/// substring_similarity: longest common substring length over the longer length, two empty slices are identical.
+fn substring_similarity<T: Eq>(a: &[T], b: &[T]) -> f64 {
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+    longest_common_substring(a, b).len as f64 / max_len as f64
+}
+
+/// lcs_substrings: the distinct longest common substrings of `s1` and `s2` over chars, in order of first
+/// occurrence in `s1`.
+pub fn lcs_substrings(s1: &str, s2: &str) -> Vec<String> {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    let common = longest_common_substring(&a, &b);
+    let mut found: Vec<String> = Vec::new();
+    for (i, _) in common.positions {
+        let s: String = a[i..i + common.len].iter().collect();
+        if !found.contains(&s) {
+            found.push(s);
+        }
+    }
+    found
+}
+
+/// LcsSubsequence implements `StringMetric` with `lcs_subsequence_similarity`; the raw score is the lcs length.
+pub struct LcsSubsequence;
+
+/// LcsSubstring implements `StringMetric` with `lcs_substring_similarity`; the raw score is the substring length.
+pub struct LcsSubstring;
+
+impl StringMetric for LcsSubsequence {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+        lcs_subsequence_len(&a, &b) as f64
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        lcs_subsequence_similarity(s1, s2)
+    }
+}
+
+impl StringMetric for LcsSubstring {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+        longest_common_substring(&a, &b).len as f64
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        lcs_substring_similarity(s1, s2)
+    }
+}
+
+impl UnitMetric for LcsSubsequence {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        lcs_subsequence_len(a, b) as f64
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        subsequence_similarity(a, b)
+    }
+}
+
+impl UnitMetric for LcsSubstring {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        longest_common_substring(a, b).len as f64
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        substring_similarity(a, b)
+    }
+}
+
+#[allow(clippy::float_cmp)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn on_lcs_subsequence() {
+        assert_eq!(lcs_subsequence_len(&chars("ABCBDAB"), &chars("BDCABA")), 4);
+        assert_eq!(lcs_subsequence_len(&chars(""), &chars("abc")), 0);
+        assert_eq!(lcs_subsequence_distance("kitten", "sitting"), 5);
+        assert_eq!(lcs_subsequence_distance("Zürich", "Zurich"), 2);
+        assert_eq!(lcs_subsequence_similarity("abcd", "acbd"), 0.75);
+        assert_eq!(lcs_subsequence_similarity("", ""), 1.0);
+        let (a, b) = (
+            vec!["black", "leather", "wallet"],
+            vec!["black", "slim", "wallet"],
+        );
+        assert_eq!(lcs_subsequence_len(&a, &b), 2);
+    }
+
+    #[test]
+    fn on_longest_common_substring() {
+        let common = longest_common_substring(&chars("ABABC"), &chars("BABCA"));
+        assert_eq!(common.len, 4);
+        assert_eq!(common.positions, vec![(1, 0)]);
+
+        // every occurrence of every maximal substring
+        let common = longest_common_substring(&chars("abxcdyab"), &chars("cdzab"));
+        assert_eq!(common.len, 2);
+        assert_eq!(common.positions, vec![(0, 3), (3, 0), (6, 3)]);
+        assert_eq!(lcs_substrings("abxcdyab", "cdzab"), vec!["ab", "cd"]);
+
+        let none = longest_common_substring(&chars("abc"), &chars("xyz"));
+        assert_eq!((none.len, none.positions.len()), (0, 0));
+    }
+
+    #[test]
+    fn on_lcs_metrics() {
+        assert_eq!(lcs_substring_similarity("Café du Port", "Café"), 4.0 / 12.0);
+        assert_eq!(lcs_substring_similarity("", ""), 1.0);
+        assert_eq!(LcsSubstring.score("POL-1024", "POL-1O24"), 5.0);
+        assert_eq!(LcsSubsequence.score("POL-1024", "POL-1O24"), 7.0);
+        assert!(
+            LcsSubsequence.similarity("POL-1024", "POL-1O24")
+                > LcsSubstring.similarity("POL-1024", "POL-1O24")
+        );
+    }
+}
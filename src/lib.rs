@@ -1,8 +1,21 @@
+pub mod alignment;
+pub mod confusion;
 pub mod cosine;
+pub mod divergence;
+pub mod edit_distance;
 pub mod hamming;
 pub mod jaro_winkler;
+pub mod language_model;
+pub mod lcs;
+pub mod metric;
+pub mod monge_elkan;
 pub mod ngram;
+pub mod normalize;
+pub mod ratcliff_obershelp;
+pub mod segment;
+pub mod tfidf;
 pub mod tokenize;
+pub mod weighted_edit;
 #[allow(clippy::float_cmp)]
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,83 @@
+use std::borrow::Cow;
+
+/// StringMetric is the common interface shared by the string comparison algorithms in this crate,
+/// so scorers can be swapped behind `dyn StringMetric` or generics.
+///
+/// * `score` is the raw output of the underlying algorithm (e.g. 0-100 for `TokenCmp`, differing bits for Hamming).
+/// * `similarity` is normalized to [0,1], higher score is more similar.
+/// * `distance` is normalized to [0,1], higher score is less similar.
+pub trait StringMetric {
+    /// score: raw output of the underlying algorithm.
+    fn score(&self, s1: &str, s2: &str) -> f64;
+    /// similarity: normalized to [0,1], higher score is more similar.
+    fn similarity(&self, s1: &str, s2: &str) -> f64;
+    /// distance: 1 - similarity. higher score is less similar.
+    fn distance(&self, s1: &str, s2: &str) -> f64 {
+        1.0 - self.similarity(s1, s2)
+    }
+}
+
+/// UnitMetric is a `StringMetric` that also compares strings already segmented into units other than chars,
+/// e.g. the grapheme clusters of `segment::graphemes`; see `segment::Segmented` to opt a metric into them.
+pub trait UnitMetric: StringMetric {
+    /// score_units: `score` over units instead of chars.
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64;
+    /// similarity_units: `similarity` over units instead of chars.
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hamming::Hamming;
+    use crate::jaro_winkler::{Jaro, JaroWinkler};
+    use crate::ngram::{NGramCosine, NGramJaccard};
+    use crate::tokenize::{TokenPartialRatio, TokenRatio, TokenSetRatio, TokenSortRatio};
+
+    fn metrics() -> Vec<Box<dyn StringMetric>> {
+        vec![
+            Box::new(Jaro),
+            Box::new(JaroWinkler::default()),
+            Box::new(NGramJaccard {
+                n: 2,
+                padding: None,
+            }),
+            Box::new(NGramCosine {
+                n: 2,
+                padding: None,
+            }),
+            Box::new(TokenRatio),
+            Box::new(TokenPartialRatio),
+            Box::new(TokenSortRatio),
+            Box::new(TokenSetRatio),
+            Box::new(Hamming),
+        ]
+    }
+
+    #[test]
+    fn on_metric_identity() {
+        for m in metrics() {
+            assert!((m.similarity("Caesars Palace", "Caesars Palace") - 1.0).abs() < 1e-9);
+            assert!(m.distance("Caesars Palace", "Caesars Palace").abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn on_metric_normalized_range() {
+        for m in metrics() {
+            let sim = m.similarity("Ceasers Palace", "Caesars Palace");
+            assert!((0.0..=1.0).contains(&sim));
+            assert!((m.distance("Ceasers Palace", "Caesars Palace") - (1.0 - sim)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn on_metric_raw_score() {
+        assert_eq!(TokenRatio.score("hello b", "hello"), 83.0);
+        assert_eq!(Hamming.score("abc", "abd"), 3.0);
+        assert_eq!(
+            JaroWinkler::default().score("MARTHA", "MARHTA"),
+            JaroWinkler::default().similarity("MARTHA", "MARHTA")
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::metric::StringMetric;
+use crate::tokenize::{AlphaNumericTokenizer, TokenizerSeq};
+
+/// Symmetry selects how `monge_elkan_symmetric` combines the two asymmetric directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symmetry {
+    /// mean of `monge_elkan(s1, s2)` and `monge_elkan(s2, s1)`.
+    Average,
+    /// max of `monge_elkan(s1, s2)` and `monge_elkan(s2, s1)`.
+    Max,
+}
+
+/// monge_elkan: hybrid token similarity, the mean over tokens of `s1` of the best `sim` score
+/// against any token of `s2`. Tokens come from `AlphaNumericTokenizer::sequencer`, so they are lowercased.
+/// higher score is more similar. Asymmetric: abbreviated tokens in `s1` ("AMS") are matched against
+/// their long form in `s2` ("Amsterdam"), but extra tokens in `s2` are not penalized.
+///
+///    let jw = JaroWinkler::default();
+///    monge_elkan(s1, s2, &|a, b| jw.similarity(a, b))
+///
+/// References:
+///
+/// * [The field matching problem: algorithms and applications](https://www.aaai.org/Papers/KDD/1996/KDD96-044.pdf)
+/// * [A Comparison of String Distance Metrics for Name-Matching Tasks](https://www.cs.cmu.edu/~wcohen/postscript/ijcai-ws-2003.pdf)
+pub fn monge_elkan(s1: &str, s2: &str, sim: &dyn Fn(&str, &str) -> f64) -> f64 {
+    let an = AlphaNumericTokenizer;
+    monge_elkan_tokens(&an.sequencer(s1), &an.sequencer(s2), sim)
+}
+
+/// monge_elkan_symmetric: combines both directions of `monge_elkan` with `symmetry`. higher score is more similar.
+pub fn monge_elkan_symmetric(
+    s1: &str,
+    s2: &str,
+    sim: &dyn Fn(&str, &str) -> f64,
+    symmetry: Symmetry,
+) -> f64 {
+    let an = AlphaNumericTokenizer;
+    let (t1, t2) = (an.sequencer(s1), an.sequencer(s2));
+    let (forward, backward) = (
+        monge_elkan_tokens(&t1, &t2, sim),
+        monge_elkan_tokens(&t2, &t1, sim),
+    );
+    match symmetry {
+        Symmetry::Average => (forward + backward) / 2.0,
+        Symmetry::Max => forward.max(backward),
+    }
+}
+
+/// monge_elkan_tokens: `monge_elkan` over already tokenized inputs; no tokens on either side is identical,
+/// no tokens on one side is 0.0.
+pub fn monge_elkan_tokens<S: AsRef<str>>(
+    t1: &[S],
+    t2: &[S],
+    sim: &dyn Fn(&str, &str) -> f64,
+) -> f64 {
+    if t1.is_empty() && t2.is_empty() {
+        return 1.0;
+    }
+    if t1.is_empty() || t2.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = t1
+        .iter()
+        .map(|a| {
+            t2.iter()
+                .map(|b| sim(a.as_ref(), b.as_ref()))
+                .fold(0.0, f64::max)
+        })
+        .sum();
+    total / t1.len() as f64
+}
+
+/// MongeElkan implements `StringMetric` with `monge_elkan_symmetric` using `inner` similarity per token pair;
+/// the raw score is the similarity.
+pub struct MongeElkan<M: StringMetric> {
+    pub inner: M,
+    pub symmetry: Symmetry,
+}
+
+impl<M: StringMetric> StringMetric for MongeElkan<M> {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        self.similarity(s1, s2)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        monge_elkan_symmetric(s1, s2, &|a, b| self.inner.similarity(a, b), self.symmetry)
+    }
+}
+
+#[allow(clippy::float_cmp)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jaro_winkler::JaroWinkler;
+
+    fn jw(a: &str, b: &str) -> f64 {
+        JaroWinkler::default().similarity(a, b)
+    }
+
+    #[test]
+    fn on_monge_elkan_amstel() {
+        let sabre = "INTERCONTINENTAL AMSTEL AMS";
+        let ean = "InterContinental Amstel Amsterdam";
+        let forward = monge_elkan(sabre, ean, &jw);
+        assert!(forward > 0.94);
+        assert!(forward > monge_elkan(sabre, "Hilton Amsterdam Airport", &jw));
+    }
+
+    #[test]
+    fn on_monge_elkan_asymmetric() {
+        let short = "Amstel";
+        let long = "InterContinental Amstel Amsterdam";
+        assert_eq!(monge_elkan(short, long, &jw), 1.0);
+        assert!(monge_elkan(long, short, &jw) < 1.0);
+        let avg = monge_elkan_symmetric(short, long, &jw, Symmetry::Average);
+        let max = monge_elkan_symmetric(short, long, &jw, Symmetry::Max);
+        assert_eq!(max, 1.0);
+        assert!(avg < max);
+    }
+
+    #[test]
+    fn on_monge_elkan_empty() {
+        assert_eq!(monge_elkan("", "", &jw), 1.0);
+        assert_eq!(monge_elkan("&&", "hotel", &jw), 0.0);
+        let empty: Vec<&str> = Vec::new();
+        assert_eq!(monge_elkan_tokens(&["hotel"], &empty, &jw), 0.0);
+    }
+
+    #[test]
+    fn on_monge_elkan_metric() {
+        let me = MongeElkan {
+            inner: JaroWinkler::default(),
+            symmetry: Symmetry::Average,
+        };
+        assert_eq!(me.similarity("Hotel Amstel", "amstel hotel"), 1.0);
+        assert_eq!(me.distance("Hotel Amstel", "amstel hotel"), 0.0);
+    }
+}
@@ -1,16 +1,389 @@
-use crate::cosine::{Qgram, QgramVec};
-use array_tool::vec::{Intersect, Union};
+use crate::metric::{StringMetric, UnitMetric};
 use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::hash::Hash;
 
 // REFACTOR ngram to deal with str lifetime Cow
 
+/// char_grams: every run of `n` consecutive chars in `s`, borrowed from `s`; slices on char boundaries
+/// so multi-byte input ("Zürich") is safe. Empty when `s` has fewer than `n` chars; panics when `n` is 0.
+pub fn char_grams(s: &str, n: usize) -> Vec<Cow<'_, str>> {
+    assert!(n > 0, "n-gram size must be at least 1");
+    let bounds: Vec<usize> = s
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(s.len()))
+        .collect();
+    let chars = bounds.len() - 1;
+    if chars < n {
+        return Vec::new();
+    }
+    (0..=chars - n)
+        .map(|i| Cow::from(&s[bounds[i]..bounds[i + n]]))
+        .collect()
+}
+
+/// Padding adds `n - 1` `start` sentinels before and `n - 1` `end` sentinels after a string before
+/// extracting n-grams, so prefixes and suffixes get as many grams as inner chars, and strings shorter
+/// than `n` still have grams. The default sentinels are `#` and `$`; pick chars absent from the data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Padding {
+    pub start: char,
+    pub end: char,
+}
+
+impl Default for Padding {
+    fn default() -> Self {
+        Padding {
+            start: '#',
+            end: '$',
+        }
+    }
+}
+
+/// padded_char_grams: `char_grams` of `s` padded with `padding`; grams touching a sentinel are owned,
+/// the rest are borrowed from `s`. Empty when `s` is empty and `n` is 1, since there is no padding then;
+/// panics when `n` is 0.
+///
+///    padded_char_grams("AMS", 3, &Padding::default()) == ["##A", "#AM", "AMS", "MS$", "S$$"]
+pub fn padded_char_grams<'a>(s: &'a str, n: usize, padding: &Padding) -> Vec<Cow<'a, str>> {
+    assert!(n > 0, "n-gram size must be at least 1");
+    let pad = n - 1;
+    let bounds: Vec<usize> = s
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(s.len()))
+        .collect();
+    let chars = bounds.len() - 1;
+    let padded: Vec<char> = std::iter::repeat_n(padding.start, pad)
+        .chain(s.chars())
+        .chain(std::iter::repeat_n(padding.end, pad))
+        .collect();
+    if padded.len() < n {
+        return Vec::new();
+    }
+    (0..=padded.len() - n)
+        .map(|i| {
+            if i >= pad && i + n <= pad + chars {
+                Cow::from(&s[bounds[i - pad]..bounds[i - pad + n]])
+            } else {
+                Cow::from(padded[i..i + n].iter().collect::<String>())
+            }
+        })
+        .collect()
+}
+
+/// unit_grams: every run of `n` consecutive units, e.g. `segment::graphemes`, joined into one owned gram;
+/// with `padding`, `n - 1` sentinels are added on each side as in `padded_char_grams`. Panics when `n` is 0.
+pub fn unit_grams<'a>(
+    units: &[Cow<str>],
+    n: usize,
+    padding: Option<&Padding>,
+) -> Vec<Cow<'a, str>> {
+    assert!(n > 0, "n-gram size must be at least 1");
+    let (start, end, pad) = match padding {
+        Some(p) => (p.start.to_string(), p.end.to_string(), n - 1),
+        None => (String::new(), String::new(), 0),
+    };
+    let padded: Vec<&str> = std::iter::repeat_n(start.as_str(), pad)
+        .chain(units.iter().map(|u| u.as_ref()))
+        .chain(std::iter::repeat_n(end.as_str(), pad))
+        .collect();
+    padded.windows(n).map(|w| Cow::from(w.concat())).collect()
+}
+
+/// slice_grams: every run of `n` consecutive elements of `a`, e.g. token or attribute code n-grams.
+/// Empty when `a` has fewer than `n` elements; panics when `n` is 0.
+pub fn slice_grams<T>(a: &[T], n: usize) -> Vec<&[T]> {
+    assert!(n > 0, "n-gram size must be at least 1");
+    a.windows(n).collect()
+}
+
+/// ngram_jaccard_similarity_slices: jaccard similarity of the distinct `slice_grams` of `a` and `b`, within [0,1];
+/// 0.0 when only one has a gram. When neither has one, the inputs are compared as is: 1.0 when equal,
+/// 0.0 otherwise. higher score is more similar.
+pub fn ngram_jaccard_similarity_slices<T: Eq + Hash>(a: &[T], b: &[T], n: usize) -> f64 {
+    let ga: HashSet<&[T]> = slice_grams(a, n).into_iter().collect();
+    let gb: HashSet<&[T]> = slice_grams(b, n).into_iter().collect();
+    let union = ga.union(&gb).count();
+    if union == 0 {
+        return short_similarity(a == b);
+    }
+    ga.intersection(&gb).count() as f64 / union as f64
+}
+
+/// short_similarity: the score of two inputs with no grams, too short for `n`: 1.0 when they are equal,
+/// 0.0 otherwise, since no gram can tell them apart.
+fn short_similarity(equal: bool) -> f64 {
+    if equal {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// SparseProfile is the gram counts of a single string as a vector sorted by gram, so two profiles are
+/// intersected, united and compared with one linear merge instead of dense vectors over the union.
+/// Build the query profile once and score it against many candidates:
+///
+///    let query = SparseProfile::from_str("Zürich Marriott", 3);
+///    let sim = query.jaccard_similarity(&SparseProfile::from_str(title, 3));
+///
+/// The set measures (Jaccard, cosine, Dice, overlap, Tversky) ignore counts; the q-gram distance, the
+/// multiset measures and the `divergence` distances use them, so repeated grams ("aaaa") are kept.
+/// A profile does not keep its string, so a profile without grams scores 0.0 against any profile,
+/// another empty one included; the metrics compare the strings then.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseProfile<'a> {
+    pub grams: Vec<(Cow<'a, str>, usize)>,
+    pub total: usize,
+}
+
+impl<'a> SparseProfile<'a> {
+    pub fn from_str(s: &'a str, n: usize) -> Self {
+        SparseProfile::from_grams(char_grams(s, n))
+    }
+
+    /// padded: the profile of `padded_char_grams`.
+    pub fn padded(s: &'a str, n: usize, padding: &Padding) -> Self {
+        SparseProfile::from_grams(padded_char_grams(s, n, padding))
+    }
+
+    /// new: `from_str` or `padded` depending on `padding`.
+    pub fn new(s: &'a str, n: usize, padding: Option<&Padding>) -> Self {
+        match padding {
+            Some(padding) => SparseProfile::padded(s, n, padding),
+            None => SparseProfile::from_str(s, n),
+        }
+    }
+
+    /// from_grams: the profile of already extracted grams, e.g. from `unit_grams`.
+    pub fn from_grams(mut grams: Vec<Cow<'a, str>>) -> Self {
+        let total = grams.len();
+        grams.sort_unstable();
+        let mut counted: Vec<(Cow<'a, str>, usize)> = Vec::with_capacity(grams.len());
+        for gram in grams {
+            match counted.last_mut() {
+                Some((last, c)) if *last == gram => *c += 1,
+                _ => counted.push((gram, 1)),
+            }
+        }
+        SparseProfile {
+            grams: counted,
+            total,
+        }
+    }
+
+    /// len: distinct grams.
+    pub fn len(&self) -> usize {
+        self.grams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.grams.is_empty()
+    }
+
+    /// count: occurrences of `gram`, 0 when absent.
+    pub fn count(&self, gram: &str) -> usize {
+        self.grams
+            .binary_search_by(|(g, _)| g.as_ref().cmp(gram))
+            .map_or(0, |i| self.grams[i].1)
+    }
+
+    /// probability: maximum likelihood estimate count / total, 0.0 for an empty profile.
+    pub fn probability(&self, gram: &str) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.count(gram) as f64 / self.total as f64
+    }
+
+    /// merge: calls `f` with the counts in `self` and `other` (0 when absent) of every gram in either, in gram order.
+    pub(crate) fn merge<F: FnMut(usize, usize)>(&self, other: &SparseProfile, mut f: F) {
+        let (a, b) = (&self.grams, &other.grams);
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            match a[i].0.cmp(&b[j].0) {
+                Ordering::Less => {
+                    f(a[i].1, 0);
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    f(0, b[j].1);
+                    j += 1;
+                }
+                Ordering::Equal => {
+                    f(a[i].1, b[j].1);
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        a[i..].iter().for_each(|g| f(g.1, 0));
+        b[j..].iter().for_each(|g| f(0, g.1));
+    }
+
+    /// intersection_len: distinct grams in both profiles.
+    pub fn intersection_len(&self, other: &SparseProfile) -> usize {
+        let mut shared = 0;
+        self.merge(other, |a, b| {
+            if a > 0 && b > 0 {
+                shared += 1
+            }
+        });
+        shared
+    }
+
+    /// union_len: distinct grams in either profile.
+    pub fn union_len(&self, other: &SparseProfile) -> usize {
+        self.len() + other.len() - self.intersection_len(other)
+    }
+
+    /// dot: sum over grams of the product of counts.
+    pub fn dot(&self, other: &SparseProfile) -> f64 {
+        let mut dot = 0.0;
+        self.merge(other, |a, b| dot += (a * b) as f64);
+        dot
+    }
+
+    /// norm: euclidean norm of the counts.
+    pub fn norm(&self) -> f64 {
+        self.grams
+            .iter()
+            .map(|g| (g.1 * g.1) as f64)
+            .sum::<f64>()
+            .sqrt()
+    }
+
+    /// empty_similarity: 0.0 when either side has no grams. A profile does not keep its string, so two empty
+    /// profiles share nothing; `similarity_with` compares the strings instead.
+    fn empty_similarity(&self, other: &SparseProfile) -> Option<f64> {
+        if self.is_empty() || other.is_empty() {
+            return Some(0.0);
+        }
+        None
+    }
+
+    /// similarity_with: `measure` of the profiles of `s1` and `s2`, or 1.0 for equal and 0.0 for unequal
+    /// strings when neither has a gram, e.g. two strings shorter than `n`.
+    fn similarity_with<'b, F>(&self, other: &SparseProfile<'b>, equal: bool, measure: F) -> f64
+    where
+        F: FnOnce(&Self, &SparseProfile<'b>) -> f64,
+    {
+        if self.is_empty() && other.is_empty() {
+            return short_similarity(equal);
+        }
+        measure(self, other)
+    }
+
+    /// jaccard_similarity: |X∩Y| / |X∪Y| over distinct grams. higher score is more similar.
+    pub fn jaccard_similarity(&self, other: &SparseProfile) -> f64 {
+        if let Some(sim) = self.empty_similarity(other) {
+            return sim;
+        }
+        let shared = self.intersection_len(other);
+        shared as f64 / (self.len() + other.len() - shared) as f64
+    }
+
+    /// cosine_similarity: |X∩Y| / sqrt(|X| |Y|), the cosine of presence vectors. higher score is more similar.
+    pub fn cosine_similarity(&self, other: &SparseProfile) -> f64 {
+        if let Some(sim) = self.empty_similarity(other) {
+            return sim;
+        }
+        self.intersection_len(other) as f64 / ((self.len() * other.len()) as f64).sqrt()
+    }
+
+    /// dice_similarity: 2|X∩Y| / (|X| + |Y|). higher score is more similar.
+    pub fn dice_similarity(&self, other: &SparseProfile) -> f64 {
+        if let Some(sim) = self.empty_similarity(other) {
+            return sim;
+        }
+        2.0 * self.intersection_len(other) as f64 / (self.len() + other.len()) as f64
+    }
+
+    /// overlap_similarity: |X∩Y| / min(|X|, |Y|), 1.0 when one gram set is a subset of the other.
+    /// higher score is more similar.
+    pub fn overlap_similarity(&self, other: &SparseProfile) -> f64 {
+        if let Some(sim) = self.empty_similarity(other) {
+            return sim;
+        }
+        self.intersection_len(other) as f64 / self.len().min(other.len()) as f64
+    }
+
+    /// tversky_similarity: |X∩Y| / (|X∩Y| + alpha|X-Y| + beta|Y-X|) with `self` as `X`, see
+    /// `NGram::tversky_similarity`. higher score is more similar.
+    pub fn tversky_similarity(&self, other: &SparseProfile, alpha: f64, beta: f64) -> f64 {
+        if let Some(sim) = self.empty_similarity(other) {
+            return sim;
+        }
+        let shared = self.intersection_len(other);
+        let i = shared as f64;
+        let denom = i + alpha * (self.len() - shared) as f64 + beta * (other.len() - shared) as f64;
+        if denom == 0.0 {
+            // no shared grams and zero weights
+            return 0.0;
+        }
+        i / denom
+    }
+
+    /// qgram_distance: Ukkonen's q-gram distance, the L1 distance between the count profiles,
+    /// sum over grams of |count in self - count in other|. higher score is less similar.
+    ///
+    /// References:
+    ///
+    /// * [Ukkonen, Approximate string-matching with q-grams and maximal matches](https://doi.org/10.1016/0304-3975(92)90143-4)
+    pub fn qgram_distance(&self, other: &SparseProfile) -> usize {
+        let mut distance = 0;
+        self.merge(other, |a, b| distance += a.abs_diff(b));
+        distance
+    }
+
+    /// qgram_similarity: 1 - qgram_distance / (total of self + total of other), within [0,1].
+    /// higher score is more similar.
+    pub fn qgram_similarity(&self, other: &SparseProfile) -> f64 {
+        if let Some(sim) = self.empty_similarity(other) {
+            return sim;
+        }
+        1.0 - self.qgram_distance(other) as f64 / (self.total + other.total) as f64
+    }
+
+    /// multiset_jaccard_similarity: weighted (multiset) Jaccard, sum of min counts / sum of max counts.
+    /// higher score is more similar.
+    pub fn multiset_jaccard_similarity(&self, other: &SparseProfile) -> f64 {
+        if let Some(sim) = self.empty_similarity(other) {
+            return sim;
+        }
+        let (mut min, mut max) = (0, 0);
+        self.merge(other, |a, b| {
+            min += a.min(b);
+            max += a.max(b);
+        });
+        min as f64 / max as f64
+    }
+
+    /// multiset_cosine_similarity: cosine of the count vectors, dot / (norm * norm), so repeated grams
+    /// weigh more. higher score is more similar.
+    pub fn multiset_cosine_similarity(&self, other: &SparseProfile) -> f64 {
+        if let Some(sim) = self.empty_similarity(other) {
+            return sim;
+        }
+        self.dot(other) / (self.norm() * other.norm())
+    }
+}
+
 /// Ngram is continuous sequence of n-items from a given sequence. The distance is the relative number of items between these two sequences.
+/// Grams are runs of `n` chars from `char_grams`, borrowed from the inputs, so accented input ("Zürich") is safe.
+/// A string shorter than `n` chars has no grams: two such strings score 1.0 when equal and 0.0 otherwise, one
+/// is 0.0 similar to anything else.
+/// Use `padded` to compare short strings and weight prefixes and suffixes.
+/// The measures are those of the `SparseProfile` of each side, `profile1` and `profile2`.
 ///
 /// References:
 ///
-///	* [N-Gram Similarity and Distance](https://webdocs.cs.ualberta.ca/~kondrak/papers/spire05.pdf)
-///	* [Wikipedia n-gram](https://en.wikipedia.org/wiki/N-gram)
-///	* [WolframAlpha n-gram](http://m.wolframalpha.com/input/?i=n-grams+%22n-gram+example+of+n-grams+in+wolfram+alpha%array_tool = "~1.0.3"&x=0&y=0)
+///    * [N-Gram Similarity and Distance](https://webdocs.cs.ualberta.ca/~kondrak/papers/spire05.pdf)
+///    * [Wikipedia n-gram](https://en.wikipedia.org/wiki/N-gram)
+///    * [WolframAlpha n-gram](http://m.wolframalpha.com/input/?i=n-grams+%22n-gram+example+of+n-grams+in+wolfram+alpha%array_tool = "~1.0.3"&x=0&y=0)
 pub struct NGram<'a> {
     pub n: usize,
     pub sv1: Vec<Cow<'a, str>>,
@@ -21,58 +394,75 @@ pub struct NGram<'a> {
     pub union: Vec<Cow<'a, str>>,
     pub intersect_len: usize,
     pub union_len: usize,
-    pub qgram: Qgram,
+    pub profile1: SparseProfile<'a>,
+    pub profile2: SparseProfile<'a>,
+    // the inputs are equal, the score when neither has a gram
+    identical: bool,
 }
 
 impl<'a> NGram<'a> {
     pub fn from_str(string1: &'a str, string2: &'a str, n: usize) -> Self {
-        let mut sv1: Vec<std::borrow::Cow<'a, str>> = Vec::new();
-        let mut sv2: Vec<std::borrow::Cow<'a, str>> = Vec::new();
-
-        for i in 0..=string1.len() - n {
-            sv1.push(Cow::from(&string1[i..(i + n)]));
-        }
-        for i in 0..=string2.len() - n {
-            sv2.push(Cow::from(&string2[i..(i + n)]));
-        }
-
-        let sa1_len = sv1.len();
-        let sa2_len = sv2.len();
-        let intersect = sv1.intersect(sv2.clone());
-        let union = sv1.union(sv2.clone());
-        let int_len = intersect.len();
-        let un_len = union.len();
+        NGram::from_grams(
+            char_grams(string1, n),
+            char_grams(string2, n),
+            n,
+            string1 == string2,
+        )
+    }
 
-        let mut qv1: Vec<f64> = Vec::with_capacity(un_len as usize);
-        let mut qv2: Vec<f64> = Vec::with_capacity(un_len as usize);
-        for c in &union {
-            if sv1.contains(c) {
-                qv1.push(1.0);
-            } else {
-                qv1.push(0.0);
-            }
+    /// padded: `from_str` over `padded_char_grams`, so "AMS" and "AMSTEL" share their prefix grams "##A", "#AM".
+    pub fn padded(string1: &'a str, string2: &'a str, n: usize, padding: &Padding) -> Self {
+        NGram::from_grams(
+            padded_char_grams(string1, n, padding),
+            padded_char_grams(string2, n, padding),
+            n,
+            string1 == string2,
+        )
+    }
 
-            if sv2.contains(c) {
-                qv2.push(1.0);
-            } else {
-                qv2.push(0.0);
-            }
+    /// new: `from_str` or `padded` depending on `padding`.
+    pub fn new(string1: &'a str, string2: &'a str, n: usize, padding: Option<&Padding>) -> Self {
+        match padding {
+            Some(padding) => NGram::padded(string1, string2, n, padding),
+            None => NGram::from_str(string1, string2, n),
         }
+    }
+
+    fn from_grams(
+        sv1: Vec<Cow<'a, str>>,
+        sv2: Vec<Cow<'a, str>>,
+        n: usize,
+        identical: bool,
+    ) -> Self {
+        let profile1 = SparseProfile::from_grams(sv1.clone());
+        let profile2 = SparseProfile::from_grams(sv2.clone());
+        // union and intersection keep first-seen order, the hash set keeps them linear in the gram count
+        let mut seen: HashSet<&Cow<'a, str>> = HashSet::with_capacity(sv1.len() + sv2.len());
+        let union: Vec<Cow<'a, str>> = sv1
+            .iter()
+            .chain(sv2.iter())
+            .filter(|g| seen.insert(*g))
+            .cloned()
+            .collect();
+        let intersect: Vec<Cow<'a, str>> = union
+            .iter()
+            .filter(|g| profile1.count(g) > 0 && profile2.count(g) > 0)
+            .cloned()
+            .collect();
 
         NGram {
-            n: n,
-            sv1: sv1,
-            sv2: sv2,
-            sv1_len: sa1_len,
-            sv2_len: sa2_len,
-            intersect: intersect,
-            union: union,
-            intersect_len: int_len,
-            union_len: un_len,
-            qgram: Qgram {
-                a: QgramVec::from_vec(qv1),
-                b: QgramVec::from_vec(qv2),
-            },
+            n,
+            sv1_len: sv1.len(),
+            sv2_len: sv2.len(),
+            intersect_len: intersect.len(),
+            union_len: union.len(),
+            sv1,
+            sv2,
+            intersect,
+            union,
+            profile1,
+            profile2,
+            identical,
         }
     }
 }
@@ -80,12 +470,12 @@ impl<'a> NGram<'a> {
 impl<'a> NGram<'a> {
     /// jaccard_distance: 1 - jaccard_similarity. higher score is less similar.
     pub fn jaccard_distance(&self) -> f64 {
-        1.0 - NGram::jaccard_similarity(&self)
+        1.0 - NGram::jaccard_similarity(self)
     }
 
     /// cosine_distance: 1 - cosine_similarity. higher score is less similar.
     pub fn cosine_distance(&self) -> f64 {
-        1.0 - NGram::cosine_similarity(&self)
+        1.0 - NGram::cosine_similarity(self)
     }
 
     /// jaccard_similarity: calculates jaccard coefficient, the similarity
@@ -93,20 +483,322 @@ impl<'a> NGram<'a> {
     /// ``` J(X,Y) = |X∩Y| / |X∪Y| ```
     /// higher score is more similar
     pub fn jaccard_similarity(&self) -> f64 {
-        ((self.intersect_len as f32) / (self.union_len as f32)).into()
+        self.profile1.similarity_with(
+            &self.profile2,
+            self.identical,
+            SparseProfile::jaccard_similarity,
+        )
+    }
+
+    /// dice_similarity: Sørensen-Dice coefficient, twice the shared grams over the grams of both sets:
+    /// ``` D(X,Y) = 2|X∩Y| / (|X| + |Y|) ```
+    /// with bigrams this is the "strike a match" similarity. higher score is more similar
+    pub fn dice_similarity(&self) -> f64 {
+        self.profile1.similarity_with(
+            &self.profile2,
+            self.identical,
+            SparseProfile::dice_similarity,
+        )
+    }
+
+    /// dice_distance: 1 - dice_similarity. higher score is less similar.
+    pub fn dice_distance(&self) -> f64 {
+        1.0 - self.dice_similarity()
+    }
+
+    /// overlap_similarity: overlap (Szymkiewicz-Simpson) coefficient, 1.0 when one gram set is a subset of the other:
+    /// ``` O(X,Y) = |X∩Y| / min(|X|, |Y|) ```
+    /// higher score is more similar
+    pub fn overlap_similarity(&self) -> f64 {
+        self.profile1.similarity_with(
+            &self.profile2,
+            self.identical,
+            SparseProfile::overlap_similarity,
+        )
+    }
+
+    /// overlap_distance: 1 - overlap_similarity. higher score is less similar.
+    pub fn overlap_distance(&self) -> f64 {
+        1.0 - self.overlap_similarity()
+    }
+
+    /// tversky_similarity: Tversky index, asymmetric weighting of the grams only in `X` (string1) by `alpha`
+    /// and only in `Y` (string2) by `beta`:
+    /// ``` T(X,Y) = |X∩Y| / (|X∩Y| + alpha|X-Y| + beta|Y-X|) ```
+    /// alpha = beta = 1 is Jaccard, alpha = beta = 0.5 is Dice; alpha = 1, beta = 0 scores how much of `X`
+    /// is in `Y`. `alpha` and `beta` must be >= 0. higher score is more similar
+    pub fn tversky_similarity(&self, alpha: f64, beta: f64) -> f64 {
+        self.profile1
+            .similarity_with(&self.profile2, self.identical, |p, q| {
+                p.tversky_similarity(q, alpha, beta)
+            })
+    }
+
+    /// tversky_distance: 1 - tversky_similarity. higher score is less similar.
+    pub fn tversky_distance(&self, alpha: f64, beta: f64) -> f64 {
+        1.0 - self.tversky_similarity(alpha, beta)
+    }
+
+    /// qgram_distance: Ukkonen's q-gram distance between the gram counts, see `SparseProfile::qgram_distance`.
+    /// Unlike the set measures, repeated grams ("aaaa" vs "aa") count. higher score is less similar.
+    pub fn qgram_distance(&self) -> usize {
+        self.profile1.qgram_distance(&self.profile2)
+    }
+
+    /// qgram_similarity: 1 - qgram_distance / total grams, see `SparseProfile::qgram_similarity`. higher score is more similar.
+    pub fn qgram_similarity(&self) -> f64 {
+        self.profile1.similarity_with(
+            &self.profile2,
+            self.identical,
+            SparseProfile::qgram_similarity,
+        )
+    }
+
+    /// multiset_cosine_similarity: cosine of gram count vectors rather than presence vectors. higher score is more similar.
+    pub fn multiset_cosine_similarity(&self) -> f64 {
+        self.profile1.similarity_with(
+            &self.profile2,
+            self.identical,
+            SparseProfile::multiset_cosine_similarity,
+        )
+    }
+
+    /// multiset_jaccard_similarity: sum of min counts / sum of max counts over grams. higher score is more similar.
+    pub fn multiset_jaccard_similarity(&self) -> f64 {
+        self.profile1.similarity_with(
+            &self.profile2,
+            self.identical,
+            SparseProfile::multiset_jaccard_similarity,
+        )
     }
 
     /// cosine_similarity: higher score is more similar
     pub fn cosine_similarity(&self) -> f64 {
-        let a = &self.qgram.a;
-        let b = &self.qgram.b;
-        (a * b).sum() / (((a * a).sum()).sqrt() * ((b * b).sum()).sqrt())
+        self.profile1.similarity_with(
+            &self.profile2,
+            self.identical,
+            SparseProfile::cosine_similarity,
+        )
+    }
+}
+
+/*
+    The n-gram metrics compare the `SparseProfile`s of grams of size `n`, padded when `padding` is set.
+    Two inputs without grams score 1.0 when equal and 0.0 otherwise. The raw score is the similarity,
+    except for `QGram`.
+*/
+
+/// NGramJaccard implements `StringMetric` with `SparseProfile::jaccard_similarity`.
+pub struct NGramJaccard {
+    pub n: usize,
+    pub padding: Option<Padding>,
+}
+
+/// NGramCosine implements `StringMetric` with `SparseProfile::cosine_similarity`.
+pub struct NGramCosine {
+    pub n: usize,
+    pub padding: Option<Padding>,
+}
+
+/// NGramDice implements `StringMetric` with `SparseProfile::dice_similarity`.
+pub struct NGramDice {
+    pub n: usize,
+    pub padding: Option<Padding>,
+}
+
+/// NGramOverlap implements `StringMetric` with `SparseProfile::overlap_similarity`.
+pub struct NGramOverlap {
+    pub n: usize,
+    pub padding: Option<Padding>,
+}
+
+/// NGramTversky implements `StringMetric` with `SparseProfile::tversky_similarity`, weighting the grams
+/// only in the first string by `alpha` and those only in the second by `beta`.
+pub struct NGramTversky {
+    pub n: usize,
+    pub padding: Option<Padding>,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+/// QGram implements `StringMetric` with Ukkonen's q-gram distance; the raw score is the distance,
+/// similarity is `SparseProfile::qgram_similarity`.
+pub struct QGram {
+    pub n: usize,
+    pub padding: Option<Padding>,
+}
+
+/// profile_similarity: `measure` of the profiles of `s1` and `s2`, see `SparseProfile::similarity_with`.
+fn profile_similarity<'a, F>(
+    n: usize,
+    padding: Option<&Padding>,
+    s1: &'a str,
+    s2: &'a str,
+    measure: F,
+) -> f64
+where
+    F: FnOnce(&SparseProfile<'a>, &SparseProfile<'a>) -> f64,
+{
+    SparseProfile::new(s1, n, padding).similarity_with(
+        &SparseProfile::new(s2, n, padding),
+        s1 == s2,
+        measure,
+    )
+}
+
+impl StringMetric for QGram {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        let padding = self.padding.as_ref();
+        SparseProfile::new(s1, self.n, padding)
+            .qgram_distance(&SparseProfile::new(s2, self.n, padding)) as f64
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        let padding = self.padding.as_ref();
+        profile_similarity(self.n, padding, s1, s2, SparseProfile::qgram_similarity)
+    }
+}
+
+impl StringMetric for NGramJaccard {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        self.similarity(s1, s2)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        let padding = self.padding.as_ref();
+        profile_similarity(self.n, padding, s1, s2, SparseProfile::jaccard_similarity)
+    }
+}
+
+impl StringMetric for NGramCosine {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        self.similarity(s1, s2)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        let padding = self.padding.as_ref();
+        profile_similarity(self.n, padding, s1, s2, SparseProfile::cosine_similarity)
+    }
+}
+
+impl StringMetric for NGramDice {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        self.similarity(s1, s2)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        let padding = self.padding.as_ref();
+        profile_similarity(self.n, padding, s1, s2, SparseProfile::dice_similarity)
+    }
+}
+
+impl StringMetric for NGramOverlap {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        self.similarity(s1, s2)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        let padding = self.padding.as_ref();
+        profile_similarity(self.n, padding, s1, s2, SparseProfile::overlap_similarity)
+    }
+}
+
+impl StringMetric for NGramTversky {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        self.similarity(s1, s2)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        let padding = self.padding.as_ref();
+        profile_similarity(self.n, padding, s1, s2, |p, q| {
+            p.tversky_similarity(q, self.alpha, self.beta)
+        })
+    }
+}
+
+/// unit_profile_similarity: `measure` of the profiles of the `unit_grams` of `a` and `b`, see
+/// `profile_similarity`.
+fn unit_profile_similarity<'a, F>(
+    n: usize,
+    padding: Option<&Padding>,
+    a: &[Cow<str>],
+    b: &[Cow<str>],
+    measure: F,
+) -> f64
+where
+    F: FnOnce(&SparseProfile<'a>, &SparseProfile<'a>) -> f64,
+{
+    SparseProfile::from_grams(unit_grams(a, n, padding)).similarity_with(
+        &SparseProfile::from_grams(unit_grams(b, n, padding)),
+        a == b,
+        measure,
+    )
+}
+
+impl UnitMetric for QGram {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        let padding = self.padding.as_ref();
+        SparseProfile::from_grams(unit_grams(a, self.n, padding))
+            .qgram_distance(&SparseProfile::from_grams(unit_grams(b, self.n, padding)))
+            as f64
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        let padding = self.padding.as_ref();
+        unit_profile_similarity(self.n, padding, a, b, SparseProfile::qgram_similarity)
+    }
+}
+
+impl UnitMetric for NGramJaccard {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        self.similarity_units(a, b)
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        let padding = self.padding.as_ref();
+        unit_profile_similarity(self.n, padding, a, b, SparseProfile::jaccard_similarity)
+    }
+}
+
+impl UnitMetric for NGramCosine {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        self.similarity_units(a, b)
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        let padding = self.padding.as_ref();
+        unit_profile_similarity(self.n, padding, a, b, SparseProfile::cosine_similarity)
+    }
+}
+
+impl UnitMetric for NGramDice {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        self.similarity_units(a, b)
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        let padding = self.padding.as_ref();
+        unit_profile_similarity(self.n, padding, a, b, SparseProfile::dice_similarity)
+    }
+}
+
+impl UnitMetric for NGramOverlap {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        self.similarity_units(a, b)
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        let padding = self.padding.as_ref();
+        unit_profile_similarity(self.n, padding, a, b, SparseProfile::overlap_similarity)
+    }
+}
+
+impl UnitMetric for NGramTversky {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        self.similarity_units(a, b)
+    }
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        let padding = self.padding.as_ref();
+        unit_profile_similarity(self.n, padding, a, b, |p, q| {
+            p.tversky_similarity(q, self.alpha, self.beta)
+        })
     }
 }
 
+#[allow(clippy::float_cmp)]
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tokenize::{AlphaNumericTokenizer, TokenizerSeq};
 
     #[test]
     fn ngram_ceaser() {
@@ -144,4 +836,293 @@ mod tests {
         );
     }
 
+    #[test]
+    fn on_ngram_multibyte() {
+        let b = NGram::from_str("Zürich", "Zurich", 2);
+        assert_eq!(b.sv1, vec!["Zü", "ür", "ri", "ic", "ch"]);
+        assert_eq!(b.intersect_len, 3);
+        assert_eq!(b.union_len, 7);
+        assert_eq!(NGram::from_str("Café", "Café", 3).jaccard_similarity(), 1.0);
+        assert!(NGram::from_str("Hôtel Café", "Hotel Cafe", 2).cosine_similarity() > 0.0);
+    }
+
+    #[test]
+    fn on_ngram_short_input() {
+        let b = NGram::from_str("ab", "abcd", 3);
+        assert_eq!(b.sv1_len, 0);
+        assert_eq!(b.jaccard_similarity(), 0.0);
+        assert_eq!(b.cosine_similarity(), 0.0);
+        // neither has a gram, the strings decide
+        assert_eq!(NGram::from_str("é", "é", 2).jaccard_similarity(), 1.0);
+        assert_eq!(NGram::from_str("", "é", 2).jaccard_similarity(), 0.0);
+        assert_eq!(NGram::from_str("X1", "X2", 3).overlap_similarity(), 0.0);
+        let jaccard = NGramJaccard {
+            n: 3,
+            padding: None,
+        };
+        assert_eq!(jaccard.similarity("X1", "X2"), 0.0);
+        assert_eq!(jaccard.similarity("X1", "X1"), 1.0);
+        let cosine = NGramCosine {
+            n: 2,
+            padding: None,
+        };
+        assert_eq!(cosine.similarity("a", "b"), 0.0);
+        assert_eq!(cosine.similarity("", ""), 1.0);
+        let p = SparseProfile::from_str("a", 2);
+        assert_eq!(p.jaccard_similarity(&p), 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "n-gram size must be at least 1")]
+    fn on_ngram_zero_size() {
+        NGram::from_str("ab", "cd", 0);
+    }
+
+    #[test]
+    fn on_padded_char_grams() {
+        let pad = Padding::default();
+        assert_eq!(
+            padded_char_grams("AMS", 3, &pad),
+            vec!["##A", "#AM", "AMS", "MS$", "S$$"]
+        );
+        assert!(matches!(
+            padded_char_grams("AMS", 3, &pad)[2],
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(padded_char_grams("é", 2, &pad), vec!["#é", "é$"]);
+        assert_eq!(padded_char_grams("", 2, &pad), vec!["#$"]);
+        assert_eq!(padded_char_grams("ab", 1, &pad), vec!["a", "b"]);
+        assert_eq!(padded_char_grams("", 1, &pad), Vec::<Cow<str>>::new());
+        assert_eq!(unit_grams(&[], 1, Some(&pad)), Vec::<Cow<str>>::new());
+    }
+
+    #[test]
+    fn on_ngram_padded() {
+        let pad = Padding::default();
+        // no trigram of "AMS" is shared unpadded beyond the whole string
+        let plain = NGram::from_str("AMS", "AMSTEL", 3);
+        let padded = NGram::padded("AMS", "AMSTEL", 3, &pad);
+        assert!(padded.intersect_len > plain.intersect_len);
+        assert!(padded.jaccard_similarity() > 0.0);
+        // short product codes
+        let codes = NGram::padded("X1", "X2", 3, &pad);
+        assert!(codes.jaccard_similarity() > 0.0 && codes.jaccard_similarity() < 1.0);
+        let jaccard = NGramJaccard {
+            n: 3,
+            padding: Some(pad),
+        };
+        assert_eq!(jaccard.similarity("X1", "X1"), 1.0);
+        assert!(jaccard.similarity("X1", "X2") < 1.0);
+    }
+
+    #[test]
+    fn on_empty_unigrams() {
+        let pad = Padding::default();
+        assert_eq!(NGram::padded("", "", 1, &pad).jaccard_similarity(), 1.0);
+        assert_eq!(NGram::from_str("", "", 1).jaccard_similarity(), 1.0);
+        for padding in [None, Some(pad)].iter() {
+            let jaccard = NGramJaccard {
+                n: 1,
+                padding: *padding,
+            };
+            let qgram = QGram {
+                n: 1,
+                padding: *padding,
+            };
+            assert_eq!(jaccard.similarity("", ""), 1.0);
+            assert_eq!(jaccard.similarity("", "a"), 0.0);
+            assert_eq!(qgram.score("", ""), 0.0);
+            assert_eq!(qgram.similarity("", "a"), 0.0);
+            assert_eq!(jaccard.similarity_units(&[], &[]), 1.0);
+        }
+    }
+
+    #[test]
+    fn on_qgram_distance() {
+        // set measures collapse repeated grams
+        let b = NGram::from_str("aaaa", "aa", 2);
+        assert_eq!(b.jaccard_similarity(), 1.0);
+        assert_eq!(b.qgram_distance(), 2);
+        assert_eq!(b.multiset_jaccard_similarity(), 1.0 / 3.0);
+        assert!(b.multiset_cosine_similarity() > 0.99);
+        // Ukkonen: "abcde" vs "abdcde", grams ab bc cd de / ab bd dc cd de
+        assert_eq!(NGram::from_str("abcde", "abdcde", 2).qgram_distance(), 3);
+        // {ab: 2, ba: 1} vs {ab: 1, ba: 1, bb: 1}
+        let (p, q) = (
+            SparseProfile::from_str("abab", 2),
+            SparseProfile::from_str("abba", 2),
+        );
+        assert_eq!(p.qgram_distance(&q), 2);
+        assert_eq!(q.qgram_distance(&p), 2);
+        assert_eq!(p.qgram_similarity(&q), 1.0 - 2.0 / 6.0);
+        assert_eq!(p.multiset_jaccard_similarity(&q), 2.0 / 4.0);
+        // neither has a gram, the strings decide
+        assert_eq!(
+            NGram::from_str("a", "b", 2).multiset_jaccard_similarity(),
+            0.0
+        );
+        assert_eq!(
+            NGram::from_str("a", "b", 2).multiset_cosine_similarity(),
+            0.0
+        );
+        assert_eq!(NGram::from_str("a", "a", 2).qgram_similarity(), 1.0);
+        let empty = SparseProfile::from_str("a", 2);
+        assert_eq!(empty.qgram_similarity(&empty), 0.0);
+    }
+
+    #[test]
+    fn on_qgram_metric() {
+        let m = QGram {
+            n: 2,
+            padding: None,
+        };
+        assert_eq!(m.score("Zürich", "Zürich"), 0.0);
+        assert_eq!(m.similarity("Zürich", "Zürich"), 1.0);
+        assert_eq!(m.similarity("", "a"), 0.0);
+        assert_eq!(m.similarity("a", "a"), 1.0);
+        assert_eq!(m.similarity("", ""), 1.0);
+        assert_eq!(m.similarity("ab", "cd"), 0.0);
+        assert!(m.similarity("Zürich", "Zurich") > m.similarity("Zürich", "Geneva"));
+    }
+
+    #[test]
+    fn on_dice_overlap_tversky() {
+        // "abcde" {ab bc cd de} vs "abdcde" {ab bd dc cd de}: 3 shared, union 6
+        let b = NGram::from_str("abcde", "abdcde", 2);
+        assert_eq!(b.dice_similarity(), 6.0 / 9.0);
+        assert_eq!(b.overlap_similarity(), 3.0 / 4.0);
+        assert_eq!(b.tversky_similarity(1.0, 1.0), b.jaccard_similarity());
+        assert_eq!(b.tversky_similarity(0.5, 0.5), b.dice_similarity());
+        // all of X is in Y
+        assert_eq!(b.tversky_similarity(1.0, 0.0), 3.0 / 4.0);
+        assert_eq!(b.tversky_similarity(0.0, 1.0), 3.0 / 5.0);
+        assert!((b.dice_distance() - 3.0 / 9.0).abs() < 1e-12);
+        // repeated grams count once
+        assert_eq!(NGram::from_str("aaaa", "aa", 2).dice_similarity(), 1.0);
+        assert_eq!(
+            NGram::from_str("ab", "cd", 2).tversky_similarity(0.0, 0.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn on_overlap_subset_names() {
+        let overlap = NGramOverlap {
+            n: 2,
+            padding: None,
+        };
+        let dice = NGramDice {
+            n: 2,
+            padding: None,
+        };
+        assert_eq!(overlap.similarity("Amstel", "InterContinental Amstel"), 1.0);
+        assert!(dice.similarity("Amstel", "InterContinental Amstel") < 1.0);
+        let tversky = NGramTversky {
+            n: 2,
+            padding: None,
+            alpha: 1.0,
+            beta: 0.0,
+        };
+        assert_eq!(tversky.similarity("Amstel", "InterContinental Amstel"), 1.0);
+        assert!(tversky.similarity("InterContinental Amstel", "Amstel") < 1.0);
+    }
+
+    #[test]
+    fn on_sparse_profile() {
+        let p = SparseProfile::from_str("abab", 2);
+        assert_eq!(p.grams, vec![(Cow::from("ab"), 2), (Cow::from("ba"), 1)]);
+        let q = SparseProfile::from_str("abba", 2);
+        assert_eq!(p.intersection_len(&q), 2);
+        assert_eq!(p.union_len(&q), 3);
+        assert_eq!(p.dot(&q), 3.0);
+        assert_eq!(p.norm(), 5f64.sqrt());
+        assert_eq!(q.len(), 3);
+        assert!(SparseProfile::from_str("a", 2).is_empty());
+    }
+
+    #[test]
+    fn on_sparse_matches_ngram() {
+        let pairs = [
+            ("Ceasers Palace", "Caesars Palace"),
+            ("Hello, world!", "Hello, porld!"),
+            ("Zürich Marriott", "Marriott Zurich"),
+            ("aaaa", "aa"),
+            ("ab", "abcd"),
+        ];
+        for &(a, b) in &pairs {
+            let ng = NGram::from_str(a, b, 2);
+            let (p, q) = (SparseProfile::from_str(a, 2), SparseProfile::from_str(b, 2));
+            assert_eq!(p.intersection_len(&q), ng.intersect_len);
+            assert_eq!(p.union_len(&q), ng.union_len);
+            assert_eq!(
+                p.jaccard_similarity(&q),
+                ng.intersect_len as f64 / ng.union_len as f64
+            );
+        }
+    }
+
+    #[test]
+    fn on_ngram_union_order() {
+        let b = NGram::from_str("abcde", "abdcde", 2);
+        assert_eq!(b.union, vec!["ab", "bc", "cd", "de", "bd", "dc"]);
+        assert_eq!(b.intersect, vec!["ab", "cd", "de"]);
+        assert_eq!(b.profile1.len(), 4);
+        assert_eq!(b.profile2.count("dc"), 1);
+    }
+
+    #[test]
+    fn on_char_grams() {
+        assert_eq!(char_grams("Zürich", 3), vec!["Zür", "üri", "ric", "ich"]);
+        assert_eq!(char_grams("ab", 3), Vec::<Cow<str>>::new());
+        assert_eq!(char_grams("", 1), Vec::<Cow<str>>::new());
+    }
+    #[test]
+    #[should_panic(expected = "n-gram size must be at least 1")]
+    fn on_char_grams_zero_size() {
+        char_grams("ab", 0);
+    }
+
+    #[test]
+    fn on_ngram_profile() {
+        let p = SparseProfile::from_str("aaaa", 2);
+        assert_eq!(p.total, 3);
+        assert_eq!(p.count("aa"), 3);
+        assert_eq!(p.count("ab"), 0);
+        assert_eq!(p.probability("aa"), 1.0);
+        assert_eq!(SparseProfile::from_str("a", 2).probability("aa"), 0.0);
+    }
+
+    #[test]
+    fn on_ngram_jaccard_slices() {
+        let an = AlphaNumericTokenizer;
+        let (t1, t2) = (
+            an.sequencer("Sony WH-1000XM4 black"),
+            an.sequencer("sony wh 1000xm4 silver"),
+        );
+        assert_eq!(slice_grams(&t1, 2).len(), 3);
+        assert_eq!(ngram_jaccard_similarity_slices(&t1, &t2, 1), 0.6);
+        assert_eq!(ngram_jaccard_similarity_slices(&t1, &t2, 2), 0.5);
+        assert_eq!(ngram_jaccard_similarity_slices(&t1, &t2, 4), 0.0);
+
+        let codes: [u16; 3] = [7, 7, 7];
+        assert_eq!(ngram_jaccard_similarity_slices(&codes, &[7, 7], 2), 1.0);
+        assert_eq!(ngram_jaccard_similarity_slices::<u16>(&[], &[], 2), 1.0);
+        assert_eq!(ngram_jaccard_similarity_slices(&[7], &[8], 2), 0.0);
+        assert_eq!(ngram_jaccard_similarity_slices(&codes, &[], 2), 0.0);
+    }
+
+    #[test]
+    fn on_unit_grams() {
+        let units: Vec<Cow<str>> = vec!["A".into(), "M".into(), "S".into()];
+        let padding = Padding::default();
+        assert_eq!(
+            unit_grams(&units, 3, Some(&padding)),
+            padded_char_grams("AMS", 3, &padding)
+        );
+        assert_eq!(unit_grams(&units, 2, None), char_grams("AMS", 2));
+        assert_eq!(unit_grams(&units, 4, None).len(), 0);
+        // a multi-char unit stays one unit of the gram
+        let units: Vec<Cow<str>> = vec!["🇫🇷".into(), "x".into()];
+        assert_eq!(unit_grams(&units, 2, None), vec!["🇫🇷x"]);
+    }
 }
@@ -0,0 +1,226 @@
+use std::borrow::Cow;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/*
+    Normalization stage for tokenizers. Feeds mix NFC and NFD input, accented and unaccented spellings
+    ("Zürich" vs "Zurich"), full-width forms and typographic ligatures; `Normalizer` maps all of these to one
+    spelling before tokenizing, so equal names produce equal tokens.
+
+    References:
+
+    * [UAX #15 Unicode Normalization Forms](https://www.unicode.org/reports/tr15/)
+    * [Unicode case folding](https://www.unicode.org/reports/tr44/#Casemapping)
+    * [Wikipedia Halfwidth and Fullwidth Forms](https://en.wikipedia.org/wiki/Halfwidth_and_Fullwidth_Forms_(Unicode_block))
+*/
+
+/// NormalizationForm is the Unicode normalization form a `Normalizer` applies: canonical composition (NFC),
+/// or compatibility composition (NFKC), which also folds ligatures, full-width forms and superscripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizationForm {
+    Nfc,
+    Nfkc,
+}
+
+/// Normalizer holds the steps of the normalization stage, applied in this order:
+///
+/// * `form`: normalize to NFC or NFKC, `None` leaves the input form alone
+/// * `fold_width`: full-width ASCII variants and the ideographic space to ASCII ("ＸＰＳ" -> "XPS")
+/// * `fold_ligatures`: ligatures to their letters ("ﬁ" -> "fi", "æ" -> "ae")
+/// * `case_fold`: `case_fold` instead of `to_lowercase` ("Straße", "STRAẞE" -> "strasse")
+/// * `strip_diacritics`: drop combining marks after canonical decomposition, plus letters with a
+///   built-in stroke ("Zürich" -> "Zurich", "Łódź" -> "Lodz")
+///
+/// The result is recomposed to `form`. The default enables every step with NFC.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Normalizer {
+    pub form: Option<NormalizationForm>,
+    pub fold_width: bool,
+    pub fold_ligatures: bool,
+    pub case_fold: bool,
+    pub strip_diacritics: bool,
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Normalizer {
+            form: Some(NormalizationForm::Nfc),
+            fold_width: true,
+            fold_ligatures: true,
+            case_fold: true,
+            strip_diacritics: true,
+        }
+    }
+}
+
+impl Normalizer {
+    /// normalize: `s` through every enabled step; borrowed when nothing changes.
+    pub fn normalize<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        let mut out: String = self.apply_form(s);
+        if self.fold_width {
+            out = out.chars().map(fold_width).collect();
+        }
+        if self.fold_ligatures {
+            out = fold_ligatures(&out);
+        }
+        if self.case_fold {
+            out = case_fold(&out);
+        }
+        if self.strip_diacritics {
+            out = strip_diacritics(&out);
+        }
+        let out = self.apply_form(&out);
+        if out == s {
+            Cow::from(s)
+        } else {
+            Cow::from(out)
+        }
+    }
+
+    fn apply_form(&self, s: &str) -> String {
+        match self.form {
+            Some(NormalizationForm::Nfc) => s.nfc().collect(),
+            Some(NormalizationForm::Nfkc) => s.nfkc().collect(),
+            None => s.to_string(),
+        }
+    }
+}
+
+/// fold_width: the ASCII char of a full-width form (U+FF01..U+FF5E) and a space for the ideographic space;
+/// any other char is returned as is.
+pub fn fold_width(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => std::char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+/// ligature: the letters of a Latin ligature, `None` for any other char.
+fn ligature(c: char) -> Option<&'static str> {
+    match c {
+        'ﬀ' => Some("ff"),
+        'ﬁ' => Some("fi"),
+        'ﬂ' => Some("fl"),
+        'ﬃ' => Some("ffi"),
+        'ﬄ' => Some("ffl"),
+        'ﬅ' | 'ﬆ' => Some("st"),
+        'æ' => Some("ae"),
+        'Æ' => Some("AE"),
+        'œ' => Some("oe"),
+        'Œ' => Some("OE"),
+        'ĳ' => Some("ij"),
+        'Ĳ' => Some("IJ"),
+        _ => None,
+    }
+}
+
+/// fold_ligatures: Latin ligatures spelled out ("ﬁ" -> "fi", "Œuvre" -> "OEuvre").
+pub fn fold_ligatures(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match ligature(c) {
+            Some(letters) => out.push_str(letters),
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+/// case_fold: case folding as lowercase of uppercase, so unlike `to_lowercase` "ß" folds to "ss",
+/// final sigma to σ and long s to s. Capital ẞ uppercases to itself, so "ß" left by lowering is spelled
+/// "ss" as well. An approximation of Unicode `CaseFolding.txt` full folding: it also folds dotless ı to i,
+/// and Cherokee to lowercase instead of uppercase.
+pub fn case_fold(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s
+        .chars()
+        .flat_map(char::to_uppercase)
+        .flat_map(char::to_lowercase)
+    {
+        match c {
+            'ß' => out.push_str("ss"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// stroke: the base letter of a letter whose diacritic is not a combining mark under decomposition.
+fn stroke(c: char) -> Option<char> {
+    match c {
+        'ø' => Some('o'),
+        'Ø' => Some('O'),
+        'ł' => Some('l'),
+        'Ł' => Some('L'),
+        'đ' => Some('d'),
+        'Đ' => Some('D'),
+        'ħ' => Some('h'),
+        'Ħ' => Some('H'),
+        _ => None,
+    }
+}
+
+/// strip_diacritics: canonical decomposition without combining marks, with stroked letters ("ø", "ł")
+/// replaced by their base letter. The result is decomposed; recompose with NFC if needed.
+pub fn strip_diacritics(s: &str) -> String {
+    s.nfd()
+        .filter(|&c| !is_combining_mark(c))
+        .map(|c| stroke(c).unwrap_or(c))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn on_normalize_default() {
+        let n = Normalizer::default();
+        assert_eq!(n.normalize("Zürich"), "zurich");
+        assert_eq!(n.normalize("Zu\u{308}rich"), "zurich");
+        assert_eq!(n.normalize("Straße"), "strasse");
+        assert_eq!(n.normalize("STRAẞE"), "strasse");
+        assert_eq!(n.normalize("STRAẞE"), n.normalize("straße"));
+        assert_eq!(n.normalize("ＸＰＳ－１３\u{3000}Ｄｅｌｌ"), "xps-13 dell");
+        assert_eq!(n.normalize("ﬁve Œuvres"), "five oeuvres");
+        assert_eq!(n.normalize("Łódź København"), "lodz kobenhavn");
+        assert_eq!(n.normalize("ΣΟΦΟΣ"), n.normalize("σοφος"));
+        // unchanged input is borrowed
+        assert!(matches!(n.normalize("zurich"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn on_normalize_steps() {
+        let nfc = Normalizer {
+            form: Some(NormalizationForm::Nfc),
+            fold_width: false,
+            fold_ligatures: false,
+            case_fold: false,
+            strip_diacritics: false,
+        };
+        assert_eq!(nfc.normalize("Zu\u{308}rich"), "Zürich");
+        assert_eq!(nfc.normalize("ﬁ"), "ﬁ");
+
+        let nfkc = Normalizer {
+            form: Some(NormalizationForm::Nfkc),
+            ..nfc
+        };
+        assert_eq!(nfkc.normalize("ﬁ ＸＰＳ"), "fi XPS");
+
+        let none = Normalizer { form: None, ..nfc };
+        assert_eq!(none.normalize("Zu\u{308}rich"), "Zu\u{308}rich");
+    }
+
+    #[test]
+    fn on_fold_helpers() {
+        assert_eq!(fold_width('Ａ'), 'A');
+        assert_eq!(fold_width('ａ'), 'a');
+        assert_eq!(fold_width('A'), 'A');
+        assert_eq!(fold_ligatures("Encyclopædia ﬂoor"), "Encyclopaedia floor");
+        assert_eq!(case_fold("MASSE Maße ſ"), "masse masse s");
+        assert_eq!(case_fold("ẞ"), "ss");
+        assert_eq!(strip_diacritics("Crème Brûlée"), "Creme Brulee");
+        assert_eq!(strip_diacritics("Ørsted"), "Orsted");
+    }
+}
@@ -1,166 +1,492 @@
+use crate::metric::{StringMetric, UnitMetric};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
 /*
 RatcliffObserhelp distance
 
-SEE tokenizer
-
  [NIST Ratcliff/Obershelp pattern recognition](https://xlinux.nist.gov/dads/HTML/ratcliffObershelp.html):
 Compute the similarity of two strings as the number of matching characters divided by the total number of characters in the two strings.
 Matching characters are those in the longest common subsequence plus, recursively, matching characters in the unmatched region on either side of the longest common subsequence.
+
+`SequenceMatcher` follows Python's `difflib.SequenceMatcher` step for step, including its junk and
+autojunk heuristics and tie breaking, so scores match a Python pipeline exactly.
+
+ * [difflib.SequenceMatcher](https://docs.python.org/3/library/difflib.html#sequencematcher-objects)
+ * [difflib source](https://github.com/python/cpython/blob/main/Lib/difflib.py)
 */
 
-/*
-// use this to replace longest_common_substring
-// Return start of commn substring in s1, start of common substring in s2, and length of substring
-// Indexes refer to character number, not index (differ for Unicode strings)
-fn longest_common_substring() -> (usize, usize, usize) {
-    //https://github.com/matthieugomez/StringDistances.jl/blob/2834265e96f18993a98a57d97e9f27a450a161d1/src/distances/RatcliffObershelp.jl#L3
-    (0, 0, 0)
+/// sequences of at least this length have their popular elements treated as junk when autojunk is on.
+const AUTOJUNK_MIN_LEN: usize = 200;
+
+/// Match is a block of `size` equal elements, `a[a..a + size] == b[b..b + size]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Match {
+    pub a: usize,
+    pub b: usize,
+    pub size: usize,
 }
-*/
 
-fn longest_common_substring<'a>(
-    shorter: &'a str,
-    longer: &'a str,
-    low1: usize,
-    high1: usize,
-    low2: usize,
-    high2: usize,
-) -> (usize, usize, usize) {
-    let longsub = &longer[low2..high2];
-    let slen = high1 - low1;
-    for size in (1..=slen + 1).rev() {
-        for start in 0..=slen - size {
-            let substr = &shorter[low1 + start..low1 + start + size];
-            let matches: Vec<(usize, &'a str)> = longsub.match_indices(substr).collect();
-            // Does this need to be sorted?
-            if let Some(&(startb, matchstr)) = matches.first() {
-                return (low1 + start, low2 + startb, matchstr.len());
-            }
+/// Tag is the kind of edit in an `Opcode`, named as in difflib.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tag {
+    /// `a[i1..i2] == b[j1..j2]`
+    Equal,
+    /// `a[i1..i2]` is replaced by `b[j1..j2]`
+    Replace,
+    /// `b[j1..j2]` is inserted at `a[i1]`, `i1 == i2`
+    Insert,
+    /// `a[i1..i2]` is deleted, `j1 == j2`
+    Delete,
+}
+
+/// Opcode is one step of an edit script turning `a` into `b`, the ranges `a[i1..i2]` and `b[j1..j2]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opcode {
+    pub tag: Tag,
+    pub i1: usize,
+    pub i2: usize,
+    pub j1: usize,
+    pub j2: usize,
+}
+
+/// opcodes: the edit script between matching blocks sorted by position and ending with the
+/// `(a.len(), b.len(), 0)` sentinel, as from `SequenceMatcher::get_opcodes`.
+/// The gap before each block is a replace, insert or delete depending on which side is empty.
+pub fn opcodes(blocks: &[Match]) -> Vec<Opcode> {
+    let (mut i, mut j) = (0, 0);
+    let mut ops = Vec::with_capacity(blocks.len() * 2);
+    for m in blocks {
+        let tag = match (i < m.a, j < m.b) {
+            (true, true) => Some(Tag::Replace),
+            (true, false) => Some(Tag::Delete),
+            (false, true) => Some(Tag::Insert),
+            (false, false) => None,
+        };
+        if let Some(tag) = tag {
+            ops.push(Opcode {
+                tag,
+                i1: i,
+                i2: m.a,
+                j1: j,
+                j2: m.b,
+            });
+        }
+        i = m.a + m.size;
+        j = m.b + m.size;
+        if m.size > 0 {
+            ops.push(Opcode {
+                tag: Tag::Equal,
+                i1: m.a,
+                i2: i,
+                j1: m.b,
+                j2: j,
+            });
         }
     }
-    (low1, low2, 0)
+    ops
 }
 
-/*
-// use this to replace matching_blocks
-fn matching_blocks() -> Vec<(usize, usize, usize)> {
-    //https://github.com/matthieugomez/StringDistances.jl/blob/2834265e96f18993a98a57d97e9f27a450a161d1/src/distances/RatcliffObershelp.jl#L31
-    vec![(0, 0, 0)]
+/// SequenceMatcher compares two slices of any hashable elements, chars or tokens, the way
+/// `difflib.SequenceMatcher` does: matching blocks are found by recursively taking the longest
+/// block of `b` elements that are not junk.
+///
+///    let (a, b): (Vec<char>, Vec<char>) = ("abcd".chars().collect(), "bcde".chars().collect());
+///    SequenceMatcher::new(&a, &b).ratio() == 0.75
+///
+/// * `isjunk`: elements of `b` for which it returns true are never the start of a match (e.g. spaces)
+/// * `autojunk`: when `b` has at least 200 elements, elements making up more than 1% of `b` are ignored
+pub struct SequenceMatcher<'a, T: Eq + Hash> {
+    a: &'a [T],
+    b: &'a [T],
+    /// b2j: indices in `b` of every element that is neither junk nor popular.
+    b2j: HashMap<&'a T, Vec<usize>>,
+    bjunk: HashSet<&'a T>,
+    bpopular: HashSet<&'a T>,
 }
-*/
 
-pub fn matching_blocks<'a>(shorter: &'a str, longer: &'a str) -> Vec<(usize, usize, usize)> {
-    let (len1, len2) = (shorter.len(), longer.len());
-    let mut queue: Vec<(usize, usize, usize, usize)> = vec![(0, len1, 0, len2)];
-    let mut matching_blocks = Vec::new();
-    while let Some((low1, high1, low2, high2)) = queue.pop() {
-        let (i, j, k) = longest_common_substring(shorter, longer, low1, high1, low2, high2);
-        if k != 0 {
-            matching_blocks.push((i, j, k));
-            if low1 < i && low2 < j {
-                queue.push((low1, i, low2, j));
+impl<'a, T: Eq + Hash> SequenceMatcher<'a, T> {
+    /// new: a matcher without junk and with autojunk, Python's `SequenceMatcher(None, a, b)`.
+    pub fn new(a: &'a [T], b: &'a [T]) -> Self {
+        SequenceMatcher::with_junk(a, b, None, true)
+    }
+
+    /// with_junk: Python's `SequenceMatcher(isjunk, a, b, autojunk)`.
+    pub fn with_junk(
+        a: &'a [T],
+        b: &'a [T],
+        isjunk: Option<&dyn Fn(&T) -> bool>,
+        autojunk: bool,
+    ) -> Self {
+        let mut b2j: HashMap<&'a T, Vec<usize>> = HashMap::new();
+        for (j, elt) in b.iter().enumerate() {
+            b2j.entry(elt).or_default().push(j);
+        }
+
+        let mut bjunk = HashSet::new();
+        if let Some(isjunk) = isjunk {
+            bjunk = b2j.keys().filter(|elt| isjunk(elt)).cloned().collect();
+            b2j.retain(|elt, _| !bjunk.contains(elt));
+        }
+
+        let mut bpopular = HashSet::new();
+        if autojunk && b.len() >= AUTOJUNK_MIN_LEN {
+            let ntest = b.len() / 100 + 1;
+            bpopular = b2j
+                .iter()
+                .filter(|(_, idxs)| idxs.len() > ntest)
+                .map(|(elt, _)| *elt)
+                .collect();
+            b2j.retain(|elt, _| !bpopular.contains(elt));
+        }
+
+        SequenceMatcher {
+            a,
+            b,
+            b2j,
+            bjunk,
+            bpopular,
+        }
+    }
+
+    /// is_junk: whether `elt` was marked junk by the `isjunk` predicate.
+    pub fn is_junk(&self, elt: &T) -> bool {
+        self.bjunk.contains(elt)
+    }
+
+    /// is_popular: whether `elt` was dropped by the autojunk heuristic.
+    pub fn is_popular(&self, elt: &T) -> bool {
+        self.bpopular.contains(elt)
+    }
+
+    /// find_longest_match: the longest matching block in `a[alo..ahi]` and `b[blo..bhi]` with no junk element,
+    /// extended on both sides by equal junk elements. Ties go to the block starting earliest in `a`, then in `b`.
+    /// `size` is 0 when nothing matches.
+    pub fn find_longest_match(&self, alo: usize, ahi: usize, blo: usize, bhi: usize) -> Match {
+        let (a, b) = (self.a, self.b);
+        let (mut besti, mut bestj, mut bestsize) = (alo, blo, 0);
+        // j2len[j]: length of the longest match ending with a[i - 1] and b[j]
+        let mut j2len: HashMap<usize, usize> = HashMap::new();
+        for (i, elt) in a.iter().enumerate().take(ahi).skip(alo) {
+            let mut newj2len = HashMap::new();
+            if let Some(idxs) = self.b2j.get(elt) {
+                for &j in idxs {
+                    if j < blo {
+                        continue;
+                    }
+                    if j >= bhi {
+                        break;
+                    }
+                    let k = j.checked_sub(1).and_then(|p| j2len.get(&p)).unwrap_or(&0) + 1;
+                    newj2len.insert(j, k);
+                    if k > bestsize {
+                        besti = i + 1 - k;
+                        bestj = j + 1 - k;
+                        bestsize = k;
+                    }
+                }
+            }
+            j2len = newj2len;
+        }
+
+        // popular elements only get here by extension, junk is glued on last
+        for junk in &[false, true] {
+            while besti > alo
+                && bestj > blo
+                && self.is_junk(&b[bestj - 1]) == *junk
+                && a[besti - 1] == b[bestj - 1]
+            {
+                besti -= 1;
+                bestj -= 1;
+                bestsize += 1;
             }
-            if i + k < high1 && j + k < high2 {
-                queue.push((i + k, high1, j + k, high2));
+            while besti + bestsize < ahi
+                && bestj + bestsize < bhi
+                && self.is_junk(&b[bestj + bestsize]) == *junk
+                && a[besti + bestsize] == b[bestj + bestsize]
+            {
+                bestsize += 1;
             }
         }
+
+        Match {
+            a: besti,
+            b: bestj,
+            size: bestsize,
+        }
     }
-    //matching_blocks.sort(); // Is this necessary?
-    let (mut i1, mut j1, mut k1) = (0, 0, 0);
-    let mut non_adjacent = Vec::new();
-    for (i2, j2, k2) in matching_blocks {
-        if i1 + k1 == i2 && j1 + k1 == j2 {
-            k1 += k2;
-        } else {
-            if k1 != 0 {
-                non_adjacent.push((i1, j1, k1));
+
+    /// get_matching_blocks: non-adjacent matching blocks in increasing order, ending with the sentinel
+    /// `Match { a: a.len(), b: b.len(), size: 0 }`.
+    pub fn get_matching_blocks(&self) -> Vec<Match> {
+        let (la, lb) = (self.a.len(), self.b.len());
+        let mut queue = vec![(0, la, 0, lb)];
+        let mut blocks = Vec::new();
+        while let Some((alo, ahi, blo, bhi)) = queue.pop() {
+            let m = self.find_longest_match(alo, ahi, blo, bhi);
+            if m.size > 0 {
+                blocks.push(m);
+                if alo < m.a && blo < m.b {
+                    queue.push((alo, m.a, blo, m.b));
+                }
+                if m.a + m.size < ahi && m.b + m.size < bhi {
+                    queue.push((m.a + m.size, ahi, m.b + m.size, bhi));
+                }
             }
-            i1 = i2;
-            j1 = j2;
-            k1 = k2;
         }
+        blocks.sort();
+
+        let mut non_adjacent: Vec<Match> = Vec::with_capacity(blocks.len() + 1);
+        for m in blocks {
+            match non_adjacent.last_mut() {
+                Some(last) if last.a + last.size == m.a && last.b + last.size == m.b => {
+                    last.size += m.size
+                }
+                _ => non_adjacent.push(m),
+            }
+        }
+        non_adjacent.push(Match {
+            a: la,
+            b: lb,
+            size: 0,
+        });
+        non_adjacent
     }
-    if k1 != 0 {
-        non_adjacent.push((i1, j1, k1));
+
+    /// get_opcodes: the edit script turning `a` into `b`, see `opcodes`.
+    pub fn get_opcodes(&self) -> Vec<Opcode> {
+        opcodes(&self.get_matching_blocks())
     }
-    non_adjacent.push((len1, len2, 0));
-    non_adjacent
-}
 
+    /// ratio: 2 * matched elements / total elements, within [0,1]; two empty sequences are identical.
+    /// higher score is more similar.
+    pub fn ratio(&self) -> f64 {
+        let matches: usize = self.get_matching_blocks().iter().map(|m| m.size).sum();
+        calculate_ratio(matches, self.a.len() + self.b.len())
+    }
 
+    /// quick_ratio: upper bound on `ratio` from the multiset intersection of `a` and `b`, ignoring order.
+    pub fn quick_ratio(&self) -> f64 {
+        let mut avail: HashMap<&T, isize> = HashMap::new();
+        for elt in self.b {
+            *avail.entry(elt).or_insert(0) += 1;
+        }
+        let mut matches = 0;
+        for elt in self.a {
+            if let Some(n) = avail.get_mut(elt) {
+                if *n > 0 {
+                    matches += 1;
+                }
+                *n -= 1;
+            }
+        }
+        calculate_ratio(matches, self.a.len() + self.b.len())
+    }
 
+    /// real_quick_ratio: upper bound on `ratio` from the lengths alone.
+    pub fn real_quick_ratio(&self) -> f64 {
+        let (la, lb) = (self.a.len(), self.b.len());
+        calculate_ratio(la.min(lb), la + lb)
+    }
+}
 
+fn calculate_ratio(matches: usize, length: usize) -> f64 {
+    if length == 0 {
+        return 1.0;
+    }
+    2.0 * matches as f64 / length as f64
+}
 
-/*
-## substring
-From the rust discourse: [substring from a string](https://users.rust-lang.org/t/how-to-get-a-substring-of-a-string/1351/11)
+/// ratcliff_obershelp_similarity: `SequenceMatcher::ratio` over the chars of `s1` and `s2`, no junk, with autojunk,
+/// the same as Python's `SequenceMatcher(None, s1, s2).ratio()`. higher score is more similar.
+pub fn ratcliff_obershelp_similarity(s1: &str, s2: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    SequenceMatcher::new(&a, &b).ratio()
+}
+
+/// ratcliff_obershelp_similarity_slices: `SequenceMatcher::ratio` over any element slices, no junk, with autojunk.
+/// higher score is more similar.
+pub fn ratcliff_obershelp_similarity_slices<T: Eq + Hash>(a: &[T], b: &[T]) -> f64 {
+    SequenceMatcher::new(a, b).ratio()
+}
 
-This looks like an interesting take....
+/// ratcliff_obershelp_distance: 1 - ratcliff_obershelp_similarity. higher score is less similar.
+pub fn ratcliff_obershelp_distance(s1: &str, s2: &str) -> f64 {
+    1.0 - ratcliff_obershelp_similarity(s1, s2)
+}
 
-use std::ops::{Bound, RangeBounds};
+/// RatcliffObershelp implements `StringMetric` with `SequenceMatcher::ratio` over chars, with or without
+/// `autojunk`; the raw score is the similarity.
+pub struct RatcliffObershelp {
+    pub autojunk: bool,
+}
 
-trait StringUtils {
-    fn substring(&self, start: usize, len: usize) -> &str;
-    fn slice(&self, range: impl RangeBounds<usize>) -> &str;
+impl StringMetric for RatcliffObershelp {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        self.similarity(s1, s2)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+        SequenceMatcher::with_junk(&a, &b, None, self.autojunk).ratio()
+    }
 }
 
-impl StringUtils for str {
-    fn substring(&self, start: usize, len: usize) -> &str {
-        let mut char_pos = 0;
-        let mut byte_start = 0;
-        let mut it = self.chars();
-        loop {
-            if char_pos == start { break; }
-            if let Some(c) = it.next() {
-                char_pos += 1;
-                byte_start += c.len_utf8();
-            }
-            else { break; }
-        }
-        char_pos = 0;
-        let mut byte_end = byte_start;
-        loop {
-            if char_pos == len { break; }
-            if let Some(c) = it.next() {
-                char_pos += 1;
-                byte_end += c.len_utf8();
-            }
-            else { break; }
-        }
-        &self[byte_start..byte_end]
+impl UnitMetric for RatcliffObershelp {
+    fn score_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        self.similarity_units(a, b)
     }
-    fn slice(&self, range: impl RangeBounds<usize>) -> &str {
-        let start = match range.start_bound() {
-            Bound::Included(bound) | Bound::Excluded(bound) => *bound,
-            Bound::Unbounded => 0,
-        };
-        let len = match range.end_bound() {
-            Bound::Included(bound) => *bound + 1,
-            Bound::Excluded(bound) => *bound,
-            Bound::Unbounded => self.len(),
-        } - start;
-        self.substring(start, len)
+    fn similarity_units(&self, a: &[Cow<str>], b: &[Cow<str>]) -> f64 {
+        SequenceMatcher::with_junk(a, b, None, self.autojunk).ratio()
     }
 }
 
-fn main() {
-    let s = "abcdèfghij";
-    // All three statements should print:
-    // "abcdè, abcdèfghij, dèfgh, dèfghij."
-    println!("{}, {}, {}, {}.",
-        s.substring(0, 5),
-        s.substring(0, 50),
-        s.substring(3, 5),
-        s.substring(3, 50));
-    println!("{}, {}, {}, {}.",
-        s.slice(..5),
-        s.slice(..50),
-        s.slice(3..8),
-        s.slice(3..));
-    println!("{}, {}, {}, {}.",
-        s.slice(..=4),
-        s.slice(..=49),
-        s.slice(3..=7),
-        s.slice(3..));
+#[allow(clippy::float_cmp)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize::{AlphaNumericTokenizer, TokenizerSeq};
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    fn m(a: usize, b: usize, size: usize) -> Match {
+        Match { a, b, size }
+    }
+
+    #[test]
+    fn on_difflib_examples() {
+        let (a, b) = (chars("abxcd"), chars("abcd"));
+        assert_eq!(
+            SequenceMatcher::new(&a, &b).get_matching_blocks(),
+            vec![m(0, 0, 2), m(3, 2, 2), m(5, 4, 0)]
+        );
+
+        let (a, b) = (
+            chars("private Thread currentThread;"),
+            chars("private volatile Thread currentThread;"),
+        );
+        let space = |c: &char| *c == ' ';
+        let s = SequenceMatcher::with_junk(&a, &b, Some(&space), true);
+        assert_eq!(
+            s.get_matching_blocks(),
+            vec![m(0, 0, 8), m(8, 17, 21), m(29, 38, 0)]
+        );
+        assert!((s.ratio() - 0.865_671_641_791_044_7).abs() < 1e-12);
+
+        let (a, b) = (chars(" abcd"), chars("abcd abcd"));
+        assert_eq!(
+            SequenceMatcher::new(&a, &b).find_longest_match(0, 5, 0, 9),
+            m(0, 4, 5)
+        );
+        let s = SequenceMatcher::with_junk(&a, &b, Some(&space), true);
+        assert_eq!(s.find_longest_match(0, 5, 0, 9), m(1, 0, 4));
+        assert!(s.is_junk(&' '));
+    }
+
+    fn op(tag: Tag, i1: usize, i2: usize, j1: usize, j2: usize) -> Opcode {
+        Opcode {
+            tag,
+            i1,
+            i2,
+            j1,
+            j2,
+        }
+    }
+
+    #[test]
+    fn on_opcodes() {
+        // difflib docs: SequenceMatcher(None, "qabxcd", "abycdf").get_opcodes()
+        let (a, b) = (chars("qabxcd"), chars("abycdf"));
+        assert_eq!(
+            SequenceMatcher::new(&a, &b).get_opcodes(),
+            vec![
+                op(Tag::Delete, 0, 1, 0, 0),
+                op(Tag::Equal, 1, 3, 0, 2),
+                op(Tag::Replace, 3, 4, 2, 3),
+                op(Tag::Equal, 4, 6, 3, 5),
+                op(Tag::Insert, 6, 6, 5, 6),
+            ]
+        );
+        let empty: Vec<char> = Vec::new();
+        assert_eq!(SequenceMatcher::new(&empty, &empty).get_opcodes(), vec![]);
+        let a: Vec<&str> = vec!["InterContinental", "Amstel", "Amsterdam"];
+        let b: Vec<&str> = vec!["Amstel", "Amsterdam", "Hotel"];
+        assert_eq!(
+            SequenceMatcher::new(&a, &b).get_opcodes(),
+            vec![
+                op(Tag::Delete, 0, 1, 0, 0),
+                op(Tag::Equal, 1, 3, 0, 2),
+                op(Tag::Insert, 3, 3, 2, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn on_ratios() {
+        let (a, b) = (chars("abcd"), chars("bcde"));
+        let s = SequenceMatcher::new(&a, &b);
+        assert_eq!(s.ratio(), 0.75);
+        assert_eq!(s.quick_ratio(), 0.75);
+        assert_eq!(s.real_quick_ratio(), 1.0);
+        let empty: Vec<char> = Vec::new();
+        assert_eq!(SequenceMatcher::new(&empty, &empty).ratio(), 1.0);
+        assert_eq!(SequenceMatcher::new(&a, &empty).ratio(), 0.0);
+    }
+
+    #[test]
+    fn on_multibyte_chars() {
+        assert!(
+            (ratcliff_obershelp_similarity("Zürich Hauptbahnhof", "Zurich HB Hauptbahnhof")
+                - 0.878_048_780_487_804_9)
+                .abs()
+                < 1e-12
+        );
+        assert_eq!(ratcliff_obershelp_distance("Café", "Café"), 0.0);
+    }
+
+    #[test]
+    fn on_autojunk() {
+        let (a, b) = (chars(&("ba".repeat(10) + "x")), chars(&"ab".repeat(150)));
+        let s = SequenceMatcher::new(&a, &b);
+        assert!(s.is_popular(&'a'));
+        assert_eq!(s.ratio(), 0.0);
+        let s = SequenceMatcher::with_junk(&a, &b, None, false);
+        assert!((s.ratio() - 0.124_610_591_900_311_53).abs() < 1e-12);
+        let (a, b) = ("ba".repeat(10), "ab".repeat(150));
+        assert_eq!(
+            RatcliffObershelp { autojunk: false }.similarity(&a, &b),
+            0.125
+        );
+        assert_eq!(RatcliffObershelp { autojunk: true }.similarity(&a, &b), 0.0);
+    }
+
+    #[test]
+    fn on_token_slices() {
+        let a: Vec<&str> = "Hotel Amstel Amsterdam".split(' ').collect();
+        let b: Vec<&str> = "Amstel Hotel Amsterdam".split(' ').collect();
+        assert!((SequenceMatcher::new(&a, &b).ratio() - 2.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn on_ratcliff_obershelp_slices() {
+        assert_eq!(
+            ratcliff_obershelp_similarity_slices(
+                &chars("GESTALT PATTERN"),
+                &chars("GESTELT PATERN")
+            ),
+            ratcliff_obershelp_similarity("GESTALT PATTERN", "GESTELT PATERN")
+        );
+        let an = AlphaNumericTokenizer;
+        let (t1, t2) = (
+            an.sequencer("Sony WH-1000XM4 black"),
+            an.sequencer("sony wh 1000xm4 silver"),
+        );
+        assert_eq!(ratcliff_obershelp_similarity_slices(&t1, &t2), 0.75);
+        assert_eq!(
+            ratcliff_obershelp_similarity_slices(&[3u8, 1, 4], &[3u8, 1, 4]),
+            1.0
+        );
+    }
 }
-*/
\ No newline at end of file
@@ -0,0 +1,186 @@
+use crate::metric::{StringMetric, UnitMetric};
+use std::borrow::Cow;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
+
+/*
+    Grapheme cluster segmentation. The char-based metrics compare `chars()`, so "é" written as e followed
+    by a combining acute is two units against the one unit of a precomposed "é", and a flag emoji is two
+    regional indicators. Splitting into extended grapheme clusters and composing each cluster to NFC makes
+    one user-perceived character one unit, so visually identical strings from different sources compare equal.
+
+    Only metrics implementing `UnitMetric` can be segmented. Three char-based metrics are left out because
+    their definition is tied to something smaller than a cluster: `Hamming` counts differing bits of the
+    utf-8 bytes, `WeightedLevenshtein` prices edits through a `CostModel` keyed by single chars, and
+    `Strcmp95` adjusts for its table of similar ASCII letters and digits after uppercasing.
+
+    References:
+
+    * [UAX #29 Unicode Text Segmentation](https://www.unicode.org/reports/tr29/)
+    * [UAX #15 Unicode Normalization Forms](https://www.unicode.org/reports/tr15/)
+*/
+
+/// Segmentation selects the unit a char-based metric compares: `Chars` (the default) or `Graphemes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Segmentation {
+    #[default]
+    Chars,
+    Graphemes,
+}
+
+impl Segmentation {
+    /// units: `s` split into chars or into `graphemes`.
+    pub fn units<'a>(&self, s: &'a str) -> Vec<Cow<'a, str>> {
+        match self {
+            Segmentation::Chars => s
+                .char_indices()
+                .map(|(i, c)| Cow::from(&s[i..i + c.len_utf8()]))
+                .collect(),
+            Segmentation::Graphemes => graphemes(s),
+        }
+    }
+}
+
+/// graphemes: the extended grapheme clusters of `s`, each composed to NFC; clusters already in NFC
+/// are borrowed from `s`.
+///
+///    graphemes("cafe\u{301} 🇫🇷") == ["c", "a", "f", "é", " ", "🇫🇷"]
+pub fn graphemes(s: &str) -> Vec<Cow<'_, str>> {
+    s.graphemes(true)
+        .map(|g| {
+            if is_nfc(g) {
+                Cow::from(g)
+            } else {
+                Cow::from(g.nfc().collect::<String>())
+            }
+        })
+        .collect()
+}
+
+/// Segmented implements `StringMetric` with `metric` over the units of `segmentation`: `Chars` is
+/// `metric` itself, `Graphemes` compares `graphemes` with `UnitMetric::similarity_units`.
+///
+///    let jaro = Segmented::graphemes(Jaro);
+///    jaro.similarity("cafe\u{301}", "café") == 1.0
+pub struct Segmented<M: UnitMetric> {
+    pub metric: M,
+    pub segmentation: Segmentation,
+}
+
+impl<M: UnitMetric> Segmented<M> {
+    /// graphemes: `metric` over grapheme clusters.
+    pub fn graphemes(metric: M) -> Self {
+        Segmented {
+            metric,
+            segmentation: Segmentation::Graphemes,
+        }
+    }
+}
+
+impl<M: UnitMetric> StringMetric for Segmented<M> {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        match self.segmentation {
+            Segmentation::Chars => self.metric.score(s1, s2),
+            Segmentation::Graphemes => self.metric.score_units(&graphemes(s1), &graphemes(s2)),
+        }
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        match self.segmentation {
+            Segmentation::Chars => self.metric.similarity(s1, s2),
+            Segmentation::Graphemes => self.metric.similarity_units(&graphemes(s1), &graphemes(s2)),
+        }
+    }
+}
+
+#[allow(clippy::float_cmp)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_distance::{DamerauLevenshtein, Levenshtein, OptimalStringAlignment};
+    use crate::jaro_winkler::{Jaro, JaroWinkler};
+    use crate::lcs::{LcsSubsequence, LcsSubstring};
+    use crate::ngram::{NGramCosine, NGramDice, NGramJaccard, NGramOverlap, NGramTversky, QGram};
+    use crate::ratcliff_obershelp::RatcliffObershelp;
+
+    fn metrics() -> Vec<Box<dyn StringMetric>> {
+        let padding = Some(Default::default());
+        vec![
+            Box::new(Segmented::graphemes(Jaro)),
+            Box::new(Segmented::graphemes(JaroWinkler::default())),
+            Box::new(Segmented::graphemes(Levenshtein)),
+            Box::new(Segmented::graphemes(OptimalStringAlignment)),
+            Box::new(Segmented::graphemes(DamerauLevenshtein)),
+            Box::new(Segmented::graphemes(LcsSubsequence)),
+            Box::new(Segmented::graphemes(LcsSubstring)),
+            Box::new(Segmented::graphemes(RatcliffObershelp { autojunk: true })),
+            Box::new(Segmented::graphemes(NGramJaccard { n: 2, padding })),
+            Box::new(Segmented::graphemes(NGramCosine { n: 2, padding })),
+            Box::new(Segmented::graphemes(NGramDice {
+                n: 3,
+                padding: None,
+            })),
+            Box::new(Segmented::graphemes(NGramOverlap { n: 2, padding })),
+            Box::new(Segmented::graphemes(NGramTversky {
+                n: 2,
+                padding,
+                alpha: 0.5,
+                beta: 0.5,
+            })),
+            Box::new(Segmented::graphemes(QGram { n: 2, padding })),
+        ]
+    }
+
+    #[test]
+    fn on_graphemes() {
+        let units = graphemes("cafe\u{301} 🇫🇷");
+        assert_eq!(units, vec!["c", "a", "f", "é", " ", "🇫🇷"]);
+        // composed from the combining sequence, the rest borrowed
+        assert!(matches!(units[3], Cow::Owned(_)));
+        assert!(matches!(units[5], Cow::Borrowed(_)));
+        // hangul jamo compose to one syllable
+        assert_eq!(graphemes("\u{1112}\u{1161}\u{11ab}"), vec!["한"]);
+        assert_eq!(Segmentation::Chars.units("e\u{301}").len(), 2);
+        assert_eq!(Segmentation::Graphemes.units("e\u{301}").len(), 1);
+        assert!(graphemes("").is_empty());
+    }
+
+    #[test]
+    fn on_segmented_visually_identical() {
+        // decomposed vs precomposed accents compare equal as graphemes
+        let (s1, s2) = ("Cafe\u{301} Ze\u{301}phyr", "Café Zéphyr");
+        for m in metrics() {
+            assert!((m.similarity(s1, s2) - 1.0).abs() < 1e-9);
+        }
+        assert!(Jaro.similarity(s1, s2) < 1.0);
+        assert!(
+            NGramJaccard {
+                n: 2,
+                padding: None
+            }
+            .similarity(s1, s2)
+                < 1.0
+        );
+        assert_eq!(Levenshtein.score(s1, s2), 4.0);
+        assert_eq!(Segmented::graphemes(Levenshtein).score(s1, s2), 0.0);
+    }
+
+    #[test]
+    fn on_segmented_flags() {
+        // 🇫🇷 and 🇫🇮 share a regional indicator but are different flags
+        let (fr, fi) = ("🇫🇷", "🇫🇮");
+        assert_eq!(Levenshtein.similarity(fr, fi), 0.5);
+        assert_eq!(Segmented::graphemes(Levenshtein).similarity(fr, fi), 0.0);
+        assert_eq!(Segmented::graphemes(Jaro).similarity(fr, fi), 0.0);
+        assert_eq!(Segmented::graphemes(Levenshtein).score("🇫🇷🇩🇪", "🇩🇪🇫🇷"), 2.0);
+        assert_eq!(
+            Segmented::graphemes(DamerauLevenshtein).score("🇫🇷🇩🇪", "🇩🇪🇫🇷"),
+            1.0
+        );
+        // chars mode is the wrapped metric
+        let chars = Segmented {
+            metric: Levenshtein,
+            segmentation: Segmentation::Chars,
+        };
+        assert_eq!(chars.similarity(fr, fi), Levenshtein.similarity(fr, fi));
+    }
+}
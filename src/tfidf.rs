@@ -0,0 +1,195 @@
+use crate::cosine::{Cosine, Qgram, QgramVec};
+use crate::metric::StringMetric;
+use crate::ngram::char_grams;
+use crate::tokenize::TokenizerSeq;
+use std::collections::{HashMap, HashSet};
+
+/// TermExtractor turns a string into the terms weighted by `TfIdf`.
+/// Every `TokenizerSeq` is a `TermExtractor` over its `sequencer` tokens; `CharGrams` extracts char n-grams.
+pub trait TermExtractor {
+    fn terms(&self, input: &str) -> Vec<String>;
+}
+
+/// CharGrams extracts char n-grams of size `n` with `ngram::char_grams`.
+pub struct CharGrams {
+    pub n: usize,
+}
+
+impl<T> TermExtractor for T
+where
+    T: for<'a> TokenizerSeq<'a>,
+{
+    fn terms(&self, input: &str) -> Vec<String> {
+        self.sequencer(input)
+            .into_iter()
+            .map(|t| t.into_owned())
+            .collect()
+    }
+}
+
+impl TermExtractor for CharGrams {
+    fn terms(&self, input: &str) -> Vec<String> {
+        char_grams(input, self.n)
+            .into_iter()
+            .map(|t| t.into_owned())
+            .collect()
+    }
+}
+
+/// TfIdf is a corpus model storing document frequencies of the terms from `extractor`, used to score pairs
+/// by TF-IDF weighted cosine so terms common across the corpus ("pack", "black", "hotel") count for less.
+///
+/// * tf is the raw count of a term in the string
+/// * idf is smoothed, `ln((1 + docs) / (1 + df)) + 1`, so terms never seen in the corpus get the highest weight
+///
+/// References:
+///
+/// * [Wikipedia tf-idf](https://en.wikipedia.org/wiki/Tf%E2%80%93idf)
+/// * [scikit-learn tf-idf term weighting](https://scikit-learn.org/stable/modules/feature_extraction.html#tfidf-term-weighting)
+pub struct TfIdf<E: TermExtractor> {
+    pub extractor: E,
+    pub docs: usize,
+    pub df: HashMap<String, usize>,
+}
+
+impl<E: TermExtractor> TfIdf<E> {
+    /// new: an empty corpus model; add documents with `add_document`.
+    pub fn new(extractor: E) -> Self {
+        TfIdf {
+            extractor,
+            docs: 0,
+            df: HashMap::new(),
+        }
+    }
+
+    /// fit: a corpus model over every document in `corpus`.
+    pub fn fit<'a, I>(extractor: E, corpus: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut model = TfIdf::new(extractor);
+        for doc in corpus {
+            model.add_document(doc);
+        }
+        model
+    }
+
+    /// add_document: counts each distinct term of `doc` once towards its document frequency.
+    pub fn add_document(&mut self, doc: &str) {
+        let distinct: HashSet<String> = self.extractor.terms(doc).into_iter().collect();
+        for term in distinct {
+            *self.df.entry(term).or_insert(0) += 1;
+        }
+        self.docs += 1;
+    }
+
+    /// idf: smoothed inverse document frequency of `term`.
+    pub fn idf(&self, term: &str) -> f64 {
+        let df = *self.df.get(term).unwrap_or(&0) as f64;
+        ((1.0 + self.docs as f64) / (1.0 + df)).ln() + 1.0
+    }
+
+    /// weights: tf * idf for every distinct term of `input`.
+    pub fn weights(&self, input: &str) -> HashMap<String, f64> {
+        let mut tf: HashMap<String, f64> = HashMap::new();
+        for term in self.extractor.terms(input) {
+            *tf.entry(term).or_insert(0.0) += 1.0;
+        }
+        for (term, w) in tf.iter_mut() {
+            *w *= self.idf(term);
+        }
+        tf
+    }
+
+    /// qgram: tf-idf vectors of `s1` and `s2` over the union of their terms.
+    pub fn qgram(&self, s1: &str, s2: &str) -> Qgram {
+        let (w1, w2) = (self.weights(s1), self.weights(s2));
+        let mut union: Vec<&String> = w1
+            .keys()
+            .chain(w2.keys().filter(|t| !w1.contains_key(*t)))
+            .collect();
+        union.sort();
+        let weight = |w: &HashMap<String, f64>, t: &String| *w.get(t).unwrap_or(&0.0);
+        Qgram {
+            a: QgramVec::from_vec(union.iter().map(|t| weight(&w1, t)).collect()),
+            b: QgramVec::from_vec(union.iter().map(|t| weight(&w2, t)).collect()),
+        }
+    }
+
+    /// cosine_similarity: tf-idf weighted cosine. higher score is more similar.
+    /// no terms on either side is identical, no terms on one side is 0.0.
+    pub fn cosine_similarity(&self, s1: &str, s2: &str) -> f64 {
+        let q = self.qgram(s1, s2);
+        let (empty1, empty2) = (q.a.iter().all(|&w| w == 0.0), q.b.iter().all(|&w| w == 0.0));
+        if empty1 && empty2 {
+            return 1.0;
+        }
+        if empty1 || empty2 {
+            return 0.0;
+        }
+        q.similarity()
+    }
+
+    /// cosine_distance: 1 - cosine_similarity. higher score is less similar.
+    pub fn cosine_distance(&self, s1: &str, s2: &str) -> f64 {
+        1.0 - self.cosine_similarity(s1, s2)
+    }
+}
+
+/// TfIdf implements `StringMetric` with `cosine_similarity`; the raw score is the similarity.
+impl<E: TermExtractor> StringMetric for TfIdf<E> {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        self.cosine_similarity(s1, s2)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        self.cosine_similarity(s1, s2)
+    }
+}
+
+#[allow(clippy::float_cmp)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize::AlphaNumericTokenizer;
+
+    fn catalog() -> Vec<&'static str> {
+        vec![
+            "Acme Black Backpack 2 Pack",
+            "Zenith Black Socks 6 Pack",
+            "Nordic Black Gloves 2 Pack",
+            "Acme Trail Backpack",
+            "Zenith White Socks",
+            "Orbit Black Mug 4 Pack",
+        ]
+    }
+
+    #[test]
+    fn on_tfidf_idf() {
+        let model = TfIdf::fit(AlphaNumericTokenizer, catalog());
+        assert_eq!(model.docs, 6);
+        assert_eq!(model.df["black"], 4);
+        assert_eq!(model.df["pack"], 4);
+        assert!(model.idf("black") < model.idf("backpack"));
+        assert!(model.idf("backpack") < model.idf("unseen"));
+    }
+
+    #[test]
+    fn on_tfidf_common_tokens_downweighted() {
+        let model = TfIdf::fit(AlphaNumericTokenizer, catalog());
+        // unweighted cosine of these token sets is 3/4
+        let shared_common = model.cosine_similarity("Acme Black Pack 2", "Orbit Black Pack 2");
+        assert!(shared_common < 0.75);
+        // unweighted cosine of these token sets is 1/2
+        let shared_rare = model.cosine_similarity("Nordic Black", "Nordic Pack");
+        assert!(shared_rare > 0.5);
+    }
+
+    #[test]
+    fn on_tfidf_char_grams() {
+        let model = TfIdf::fit(CharGrams { n: 3 }, catalog());
+        assert!((model.similarity("Zürich", "Zürich") - 1.0).abs() < 1e-9);
+        assert!(model.similarity("Backpack", "Bakcpack") > model.similarity("Backpack", "Socks"));
+        assert_eq!(model.similarity("", ""), 1.0);
+        assert_eq!(model.similarity("ab", "Backpack"), 0.0);
+    }
+}
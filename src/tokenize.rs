@@ -1,6 +1,10 @@
+use crate::metric::StringMetric;
+use crate::normalize::Normalizer;
+use crate::ratcliff_obershelp::{opcodes, Match, Opcode, SequenceMatcher};
 use array_tool::vec::{Intersect, Union, Uniq};
 use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 /*
 
 
@@ -17,38 +21,43 @@ use std::cmp::Ordering;
 
 /*
 token_sort evaluates similarity of the longest substrings (Ratcliff/Obershelp) based on sorted order of the tokens.
-pass in two strings, the sorter, and the similarity:
+pass in two strings, the tokenizer, the sorter, and the similarity:
 
-   token_sort(s1, s2, &TokenCmp::new_sort, &TokenCmp::partial_similarity)
-   token_sort(s1, s2, &TokenCmp::new_sort_join, &TokenCmp::similarity)
+   token_sort(s1, s2, &AlphaNumericTokenizer, &TokenCmp::new_sort, &TokenCmp::partial_similarity)
+   token_sort(s1, s2, &WhiteSpaceTokenizer, &TokenCmp::new_sort_join, &TokenCmp::similarity)
 
 'new_sort' is by default concat (no whitespaces in evaled strings); 'new_sort_join' will be by " ".
+any `TokenizerSeq` works, e.g. a `Pipeline` from `TokenizerBuilder`.
 */
-pub fn token_sort<'a>(
+pub fn token_sort<'a, T: TokenizerSeq<'a>>(
     t1: &'a str,
     t2: &'a str,
-    sorter: &Fn(
+    tokenizer: &T,
+    sorter: &dyn Fn(
         std::vec::Vec<std::borrow::Cow<'a, str>>,
         std::vec::Vec<std::borrow::Cow<'a, str>>,
     ) -> TokenCmp<'a>,
-    rat: &Fn(&TokenCmp<'a>) -> u8,
+    rat: &dyn Fn(&TokenCmp<'a>) -> u8,
 ) -> u8 {
-    let an = AlphaNumericTokenizer;
-    rat(&sorter(an.sequencer(t1), an.sequencer(t2)))
+    rat(&sorter(tokenizer.sequencer(t1), tokenizer.sequencer(t2)))
 }
 
 /*
 token_set evaluates similarity of the longest substrings (Ratcliff/Obershelp) based on the set of intersection and union of tokens.
-pass in two strings and the similarity:
+pass in two strings, the tokenizer, and the similarity:
 
-   token_set(s1, s2, &TokenCmp::partial_similarity)
-   token_set(s1, s2, &TokenCmp::similarity)
+   token_set(s1, s2, &AlphaNumericTokenizer, &TokenCmp::partial_similarity)
+   token_set(s1, s2, &WhiteSpaceTokenizer, &TokenCmp::similarity)
 
 'new_sort' is by default concat (no whitespaces in evaled strings); 'new_sort_join' will be by " ".
 */
-pub fn token_set<'a>(s1: &'a str, s2: &'a str, rat: &Fn(&TokenCmp<'a>) -> u8) -> u8 {
-    let an = AlphaNumericTokenizer;
-    let (p1, p2) = (an.sequencer(s1), an.sequencer(s2));
+pub fn token_set<'a, T: TokenizerSeq<'a>>(
+    s1: &'a str,
+    s2: &'a str,
+    tokenizer: &T,
+    rat: &dyn Fn(&TokenCmp<'a>) -> u8,
+) -> u8 {
+    let (p1, p2) = (tokenizer.sequencer(s1), tokenizer.sequencer(s2));
     let mut s1_i_s2 = p1.intersect(p2.clone());
     let mut s1q = p1.uniq(p2.clone()); //diff1to2
     let mut s2q = p2.uniq(p1.clone()); //diff2to1
@@ -60,7 +69,7 @@ pub fn token_set<'a>(s1: &'a str, s2: &'a str, rat: &Fn(&TokenCmp<'a>) -> u8) ->
     let s1_i_s2_u_s1q = s1_i_s2.union(s1q.clone()); //combined_1to2
     let s1_i_s2_u_s2q = s1_i_s2.union(s2q.clone()); //combined_2to1
 
-    vec![
+    [
         rat(&TokenCmp::new_set(s1_i_s2.clone(), s1_i_s2_u_s1q.clone())),
         rat(&TokenCmp::new_set(s1_i_s2, s1_i_s2_u_s2q.clone())),
         rat(&TokenCmp::new_set(s1_i_s2_u_s1q, s1_i_s2_u_s2q)),
@@ -89,15 +98,61 @@ pub trait TokenizerSeq<'a> {
     fn sequencer(&self, input: &'a str) -> Vec<std::borrow::Cow<'a, str>>;
     fn token(&self, input: &'a str) -> String;
 }
-// U8IterExt Iterator Extension for min/max u8
+/// TokenFilter maps a token to its replacement, or to `None` to drop it; `TokenizerBuilder` chains filters.
+pub trait TokenFilter {
+    fn filter<'a>(&self, token: Cow<'a, str>) -> Option<Cow<'a, str>>;
+}
+// U8IterExt Iterator Extension for max u8
 trait U8IterExt {
-    fn u8_min(&mut self) -> u8;
     fn u8_max(&mut self) -> u8;
 }
 
 //////// structs
 pub struct AlphaNumericCharFilter;
 pub struct AlphaNumericTokenizer;
+/// NormalizedTokenizer runs `normalizer` over the input before `tokenizer`, so NFD, accented, full-width and
+/// ligature spellings of a name produce the same tokens. Tokens are owned unless normalization changed nothing.
+pub struct NormalizedTokenizer<T> {
+    pub tokenizer: T,
+    pub normalizer: Normalizer,
+}
+/// TokenizerBuilder composes a `Pipeline` from a splitter `CharFilter` and a chain of `TokenFilter`s:
+///
+///    let tokenizer = TokenizerBuilder::new(AlphaNumericCharFilter)
+///        .lowercase()
+///        .stopwords(&["the", "hotel"])
+///        .stem()
+///        .build();
+pub struct TokenizerBuilder<F: CharFilter> {
+    splitter: F,
+    filters: Vec<Box<dyn TokenFilter>>,
+}
+/// Pipeline splits on `splitter` and runs every token through `filters` in order, dropping it as soon as
+/// a filter returns `None`. Positional tokens keep the offset and position given by the splitter, so
+/// dropped tokens leave gaps. Unlike `AlphaNumericTokenizer`, nothing is lowercased unless a filter does it.
+pub struct Pipeline<F: CharFilter> {
+    splitter: F,
+    filters: Vec<Box<dyn TokenFilter>>,
+}
+/// Lowercase is a `TokenFilter` lowercasing with `to_lowercase`; tokens without uppercase stay borrowed.
+pub struct Lowercase;
+/// Stopwords is a `TokenFilter` dropping the listed words; matching is exact, so put it after `Lowercase`.
+pub struct Stopwords {
+    words: HashSet<String>,
+}
+/// SStemmer is a `TokenFilter` with Harman's S-stemmer, a light English plural stemmer
+/// ("hotels" -> "hotel", "properties" -> "property"); tokens of 3 chars or less are kept as is.
+///
+/// References:
+///
+///    * [How effective is suffixing?](https://doi.org/10.1002/(SICI)1097-4571(199101)42:1%3C7::AID-ASI2%3E3.0.CO;2-P)
+pub struct SStemmer;
+/// Synonyms is a `TokenFilter` replacing a token with its canonical form ("hwy" -> "highway").
+pub struct Synonyms {
+    map: HashMap<String, String>,
+}
+/// MinLength is a `TokenFilter` dropping tokens of fewer chars than its value.
+pub struct MinLength(pub usize);
 pub struct CharTokenPosIter<'a, F: CharFilter> {
     filter: F,
     input: &'a str,
@@ -116,6 +171,14 @@ pub struct TokenCmp<'a> {
     term1: Cow<'a, str>,
     term2: Cow<'a, str>,
 }
+/// TokenRatio implements `StringMetric` with `TokenCmp::similarity`; the raw score is 0-100.
+pub struct TokenRatio;
+/// TokenPartialRatio implements `StringMetric` with `TokenCmp::partial_similarity`; the raw score is 0-100.
+pub struct TokenPartialRatio;
+/// TokenSortRatio implements `StringMetric` with `token_sort` and `TokenCmp::new_sort`; the raw score is 0-100.
+pub struct TokenSortRatio;
+/// TokenSetRatio implements `StringMetric` with `token_set` and `TokenCmp::similarity`; the raw score is 0-100.
+pub struct TokenSetRatio;
 pub struct TokenizerNaive;
 pub struct TokenizerPosSummary {
     pub sequence: String,
@@ -132,8 +195,20 @@ pub struct TokenPositional<'a> {
 pub struct TokenSequence<'a> {
     term: Cow<'a, str>,
 }
+/// WhiteSpaceCharFilter splits on Unicode whitespace only, so "C++", "3.5mm", "O'Brien" and "XPS-13" stay one token.
 pub struct WhiteSpaceCharFilter;
+/// WhiteSpaceTokenizer splits with `WhiteSpaceCharFilter` and trims sentence punctuation from both ends of
+/// a token ("(XPS-13)," -> "XPS-13", "3.5mm." -> "3.5mm"); inner punctuation and symbols are kept.
+/// Like `AlphaNumericTokenizer`, `sequencer`, `token` and `pos_summary` lowercase.
 pub struct WhiteSpaceTokenizer;
+type WhiteSpaceSeqIter<'a> = std::iter::FilterMap<
+    CharTokenSeqIter<'a, WhiteSpaceCharFilter>,
+    fn(TokenSequence<'a>) -> Option<TokenSequence<'a>>,
+>;
+type WhiteSpacePosIter<'a> = std::iter::FilterMap<
+    CharTokenPosIter<'a, WhiteSpaceCharFilter>,
+    fn(TokenPositional<'a>) -> Option<TokenPositional<'a>>,
+>;
 
 ////////////////////////////////////////////////////////////////////////////////////////
 //////// impls
@@ -142,11 +217,21 @@ impl CharFilter for AlphaNumericCharFilter {
         !c.is_alphanumeric()
     }
 }
+impl CharFilter for WhiteSpaceCharFilter {
+    fn is_char(&self, c: char) -> bool {
+        c.is_whitespace()
+    }
+}
+impl<F: CharFilter + ?Sized> CharFilter for &F {
+    fn is_char(&self, c: char) -> bool {
+        (**self).is_char(c)
+    }
+}
 impl<'a, F: CharFilter> CharTokenPosIter<'a, F> {
     pub fn new(filter: F, input: &'a str) -> Self {
         CharTokenPosIter {
-            filter: filter,
-            input: input,
+            filter,
+            input,
             byte_offset: 0,
             char_offset: 0,
             position: 0,
@@ -156,8 +241,8 @@ impl<'a, F: CharFilter> CharTokenPosIter<'a, F> {
 impl<'a, F: CharFilter> CharTokenSeqIter<'a, F> {
     pub fn new(filter: F, input: &'a str) -> Self {
         CharTokenSeqIter {
-            filter: filter,
-            input: input,
+            filter,
+            input,
             byte_offset: 0,
             char_offset: 0,
             position: 0,
@@ -184,14 +269,14 @@ impl<'a, F: CharFilter> Iterator for CharTokenPosIter<'a, F> {
                 }
             })
             .find(|&(_, (_, c))| (self.filter).is_char(c))
-            .map(|(cidx, (bidx, _))| {
+            .map(|(cidx, (bidx, sep))| {
                 let slice = &self.input[self.byte_offset + skipped_bytes..self.byte_offset + bidx];
                 let tp = TokenPositional::convert_str(
                     slice,
                     self.char_offset + skipped_chars,
                     self.position,
                 );
-                self.byte_offset += bidx + 1;
+                self.byte_offset += bidx + sep.len_utf8();
                 self.char_offset += cidx + 1;
                 self.position += 1;
                 tp
@@ -232,10 +317,10 @@ impl<'a, F: CharFilter> Iterator for CharTokenSeqIter<'a, F> {
                 }
             })
             .find(|&(_, (_, c))| (self.filter).is_char(c))
-            .map(|(cidx, (bidx, _))| {
+            .map(|(cidx, (bidx, sep))| {
                 let slice = &self.input[self.byte_offset + skipped_bytes..self.byte_offset + bidx];
                 let ts = TokenSequence::convert_str(slice);
-                self.byte_offset += bidx + 1;
+                self.byte_offset += bidx + sep.len_utf8();
                 self.char_offset += cidx + 1;
                 self.position += 1;
                 ts
@@ -301,31 +386,6 @@ impl<'a> TokenCmp<'a> {
             term2: s2.into(),
         }
     }
-
-    //RatcliffObserhelp distance; see matching_blocks
-    fn longest_common_substring(
-        shorter: &'a str,
-        longer: &'a str,
-        low1: usize,
-        high1: usize,
-        low2: usize,
-        high2: usize,
-    ) -> (usize, usize, usize) {
-        let long_subtoken = &longer[low2..high2];
-        let slen = high1 - low1;
-        for size in (1..=slen).rev() {
-            for start in 0..=slen - size {
-                let short_subtoken = &shorter[low1 + start..low1 + start + size];
-                let matches: Vec<(usize, &'a str)> =
-                    long_subtoken.match_indices(short_subtoken).collect();
-                // add sorting check...
-                if let Some(&(startb, matchstr)) = matches.first() {
-                    return (low1 + start, low2 + startb, matchstr.len());
-                }
-            }
-        }
-        (low1, low2, 0)
-    }
 }
 
 impl<'a> TokenCmp<'a> {
@@ -340,10 +400,10 @@ impl<'a> TokenCmp<'a> {
     // similarity...
     pub fn similarity(&self) -> u8 {
         //total length
-        let sumlen = (self.term1.len() + self.term2.len()) as f32;
+        let sumlen = (self.term1.chars().count() + self.term2.chars().count()) as f32;
         //find the shorter and longer
         //iter, map, sum last block size
-        let similar: usize = TokenCmp::matching_blocks(&self)
+        let similar: usize = TokenCmp::matching_blocks(self)
             .iter()
             .map(|&(_, _, s)| s)
             .sum();
@@ -355,16 +415,13 @@ impl<'a> TokenCmp<'a> {
     pub fn partial_similarity(&self) -> u8 {
         //find the shorter and longer
         //iter, map, sum last block size
-        let blocks = TokenCmp::matching_blocks(&self);
+        let blocks = TokenCmp::matching_blocks(self);
         //set max
         let mut max: u8 = 0;
         // tuple through matching subsequence
         for (i, _, k) in blocks {
             let (short, _) = self.short_long_order_by_len();
-            let sub = &short[::std::ops::Range {
-                start: i,
-                end: i + k,
-            }];
+            let sub: String = short.chars().skip(i).take(k).collect();
             /*
             stack overflow
             let r = TokenCmp::partial_similarity(&TokenCmp {
@@ -384,50 +441,65 @@ impl<'a> TokenCmp<'a> {
         }
         max
     }
-    /*
-    RatcliffObserhelp distance
-    // use this to replace matching_blocks
-    fn matching_blocks() -> Vec<(usize, usize, usize)> {
-        //https://github.com/matthieugomez/StringDistances.jl/blob/2834265e96f18993a98a57d97e9f27a450a161d1/src/distances/RatcliffObershelp.jl#L31
-    */
+    /// matching_blocks: Ratcliff/Obershelp matching blocks `(i, j, size)` between the chars of the terms in
+    /// `short_long_order_by_len` order, from `SequenceMatcher::get_matching_blocks`. Offsets count chars.
     pub fn matching_blocks(&self) -> Vec<(usize, usize, usize)> {
-        let (short, long) = &self.short_long_order_by_len();
-        let (len1, len2) = (short.len(), long.len());
-        let mut queue: Vec<(usize, usize, usize, usize)> = vec![(0, len1, 0, len2)];
-        let mut m_blocks = Vec::new();
-        while let Some((low1, high1, low2, high2)) = queue.pop() {
-            let (i, j, k) =
-                TokenCmp::longest_common_substring(short, long, low1, high1, low2, high2);
-            if k != 0 {
-                m_blocks.push((i, j, k));
-                if low1 < i && low2 < j {
-                    queue.push((low1, i, low2, j));
-                }
-                if i + k < high1 && j + k < high2 {
-                    queue.push((i + k, high1, j + k, high2));
-                }
-            }
-        }
-        // sorting... m_blocks.sort()...
-        let (mut i1, mut j1, mut k1) = (0, 0, 0);
-        let mut non_adjacent = Vec::new();
-        for (i2, j2, k2) in m_blocks {
-            if i1 + k1 == i2 && j1 + k1 == j2 {
-                k1 += k2;
-            } else {
-                if k1 != 0 {
-                    non_adjacent.push((i1, j1, k1));
-                }
-                i1 = i2;
-                j1 = j2;
-                k1 = k2;
-            }
-        }
-        if k1 != 0 {
-            non_adjacent.push((i1, j1, k1));
-        }
-        non_adjacent.push((len1, len2, 0));
-        non_adjacent
+        self.matches().iter().map(|m| (m.a, m.b, m.size)).collect()
+    }
+
+    /// opcodes: the edit script behind `similarity`, turning the first term of `short_long_order_by_len`
+    /// into the second; ranges count chars.
+    pub fn opcodes(&self) -> Vec<Opcode> {
+        opcodes(&self.matches())
+    }
+
+    fn matches(&self) -> Vec<Match> {
+        let (short, long) = self.short_long_order_by_len();
+        let (a, b): (Vec<char>, Vec<char>) = (short.chars().collect(), long.chars().collect());
+        SequenceMatcher::new(&a, &b).get_matching_blocks()
+    }
+}
+impl StringMetric for TokenRatio {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        f64::from(TokenCmp::new_from_str(s1, s2).similarity())
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        self.score(s1, s2) / 100.0
+    }
+}
+impl StringMetric for TokenPartialRatio {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        f64::from(TokenCmp::new_from_str(s1, s2).partial_similarity())
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        self.score(s1, s2) / 100.0
+    }
+}
+impl StringMetric for TokenSortRatio {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        f64::from(token_sort(
+            s1,
+            s2,
+            &AlphaNumericTokenizer,
+            &TokenCmp::new_sort,
+            &TokenCmp::similarity,
+        ))
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        self.score(s1, s2) / 100.0
+    }
+}
+impl StringMetric for TokenSetRatio {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        f64::from(token_set(
+            s1,
+            s2,
+            &AlphaNumericTokenizer,
+            &TokenCmp::similarity,
+        ))
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        self.score(s1, s2) / 100.0
     }
 }
 impl TokenizerPosSummary {
@@ -446,18 +518,37 @@ impl TokenizerPosSummary {
             positions: psns,
         }
     }
+
+    /// from_tokens: the summary of `tokens`, each term mapped to its summary string by `term`.
+    fn from_tokens<F>(tokens: Vec<TokenPositional>, term: F) -> Self
+    where
+        F: Fn(Cow<str>) -> String,
+    {
+        let tlen = tokens.len();
+        let mut tks: Vec<String> = Vec::with_capacity(tlen);
+        let mut ofs: Vec<usize> = Vec::with_capacity(tlen);
+        let mut psns: Vec<usize> = Vec::with_capacity(tlen);
+
+        for t in tokens {
+            tks.push(term(t.term));
+            ofs.push(t.start_offset);
+            psns.push(t.position)
+        }
+
+        TokenizerPosSummary::new(tks.join(" "), tks.len(), tks, ofs, psns)
+    }
 }
 //Naive Methods... begin... these are 'naive' since they do not manage memory very well... moving back and forth from stack &str to heap String can be memory ineficient... recommend using these for smaller tasks on smaller data sets.
 impl<'a> TokenizerNaive {
     /// words takes borrowed str and splits on pattern func filtering out empty slots; see tests.
-    pub fn word_splitter(t: &str, pattern: &Fn(char) -> bool) -> Vec<String> {
+    pub fn word_splitter(t: &str, pattern: &dyn Fn(char) -> bool) -> Vec<String> {
         t.split(pattern)
             .filter(|s| !s.is_empty())
             .map(|s| s.to_string())
             .collect::<Vec<String>>()
     }
 
-    pub fn tokens_lower_with_filter(t: &str, pattern: &Fn(char) -> bool) -> String {
+    pub fn tokens_lower_with_filter(t: &str, pattern: &dyn Fn(char) -> bool) -> String {
         t.split(pattern)
             .map(|s| s.to_lowercase())
             .collect::<Vec<String>>()
@@ -492,6 +583,11 @@ impl<'a> TokenizerNaive {
             .collect::<Vec<&str>>()
             .join(" ")
     }
+
+    /// pre_process_with: `pre_process` after `normalizer`, e.g. "Zu\u{308}rich" and "ZÜRICH" both to "zurich".
+    pub fn pre_process_with(s: &str, normalizer: &Normalizer) -> String {
+        TokenizerNaive::pre_process(&normalizer.normalize(s))
+    }
 }
 impl<'a> TokenizerPos<'a> for AlphaNumericTokenizer {
     type TokenIter = CharTokenPosIter<'a, AlphaNumericCharFilter>;
@@ -501,18 +597,7 @@ impl<'a> TokenizerPos<'a> for AlphaNumericTokenizer {
     }
 
     fn pos_summary(&self, tokens: Vec<TokenPositional>) -> TokenizerPosSummary {
-        let tlen = tokens.len();
-        let mut tks: Vec<String> = Vec::with_capacity(tlen as usize);
-        let mut ofs: Vec<usize> = Vec::with_capacity(tlen as usize);
-        let mut psns: Vec<usize> = Vec::with_capacity(tlen as usize);
-
-        for t in tokens {
-            tks.push(t.term.to_lowercase());
-            ofs.push(t.start_offset);
-            psns.push(t.position)
-        }
-
-        TokenizerPosSummary::new(tks.join(" "), tks.len(), tks, ofs, psns)
+        TokenizerPosSummary::from_tokens(tokens, |t| t.to_lowercase())
     }
 }
 impl<'a> TokenizerSeq<'a> for AlphaNumericTokenizer {
@@ -538,13 +623,291 @@ impl<'a> TokenizerSeq<'a> for AlphaNumericTokenizer {
         tks.join(" ")
     }
 }
+impl<T> NormalizedTokenizer<T> {
+    pub fn new(tokenizer: T, normalizer: Normalizer) -> Self {
+        NormalizedTokenizer {
+            tokenizer,
+            normalizer,
+        }
+    }
+}
+impl<'a, T> TokenizerSeq<'a> for NormalizedTokenizer<T>
+where
+    T: for<'b> TokenizerSeq<'b>,
+{
+    type TokenIter = std::vec::IntoIter<TokenSequence<'a>>;
+
+    fn tokenize_seq(&self, input: &'a str) -> Self::TokenIter {
+        let tokens: Vec<TokenSequence<'a>> = match self.normalizer.normalize(input) {
+            Cow::Borrowed(s) => self.tokenizer.tokenize_seq(s).collect(),
+            Cow::Owned(s) => self
+                .tokenizer
+                .tokenize_seq(&s)
+                .map(|t| TokenSequence {
+                    term: t.term.into_owned().into(),
+                })
+                .collect(),
+        };
+        tokens.into_iter()
+    }
+
+    fn sequencer(&self, input: &'a str) -> Vec<std::borrow::Cow<'a, str>> {
+        match self.normalizer.normalize(input) {
+            Cow::Borrowed(s) => self.tokenizer.sequencer(s),
+            Cow::Owned(s) => self
+                .tokenizer
+                .sequencer(&s)
+                .into_iter()
+                .map(|t| t.into_owned().into())
+                .collect(),
+        }
+    }
+
+    fn token(&self, input: &'a str) -> String {
+        self.tokenizer.token(&self.normalizer.normalize(input))
+    }
+}
+impl<F: CharFilter> TokenizerBuilder<F> {
+    pub fn new(splitter: F) -> Self {
+        TokenizerBuilder {
+            splitter,
+            filters: Vec::new(),
+        }
+    }
+    /// filter: appends `filter` to the chain.
+    pub fn filter<T: TokenFilter + 'static>(mut self, filter: T) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+    pub fn lowercase(self) -> Self {
+        self.filter(Lowercase)
+    }
+    /// normalize: `Normalizer` per token; to normalize before splitting (NFD input), wrap the built
+    /// `Pipeline` in a `NormalizedTokenizer` instead.
+    pub fn normalize(self, normalizer: Normalizer) -> Self {
+        self.filter(normalizer)
+    }
+    pub fn stopwords(self, words: &[&str]) -> Self {
+        self.filter(Stopwords::new(words))
+    }
+    pub fn stem(self) -> Self {
+        self.filter(SStemmer)
+    }
+    pub fn synonyms(self, pairs: &[(&str, &str)]) -> Self {
+        self.filter(Synonyms::new(pairs))
+    }
+    pub fn min_length(self, len: usize) -> Self {
+        self.filter(MinLength(len))
+    }
+    pub fn build(self) -> Pipeline<F> {
+        Pipeline {
+            splitter: self.splitter,
+            filters: self.filters,
+        }
+    }
+}
+impl<F: CharFilter> Pipeline<F> {
+    pub fn builder(splitter: F) -> TokenizerBuilder<F> {
+        TokenizerBuilder::new(splitter)
+    }
+    /// apply: `token` through every filter, `None` once one drops it.
+    fn apply<'a>(&self, token: Cow<'a, str>) -> Option<Cow<'a, str>> {
+        self.filters.iter().try_fold(token, |t, f| f.filter(t))
+    }
+}
+impl<'a, F: CharFilter> TokenizerSeq<'a> for Pipeline<F> {
+    type TokenIter = std::vec::IntoIter<TokenSequence<'a>>;
+
+    fn tokenize_seq(&self, input: &'a str) -> Self::TokenIter {
+        CharTokenSeqIter::new(&self.splitter, input)
+            .filter_map(|t| self.apply(t.term).map(|term| TokenSequence { term }))
+            .collect::<Vec<TokenSequence<'a>>>()
+            .into_iter()
+    }
+
+    fn sequencer(&self, input: &'a str) -> Vec<std::borrow::Cow<'a, str>> {
+        self.tokenize_seq(input).map(|t| t.term).collect()
+    }
+
+    fn token(&self, input: &'a str) -> String {
+        self.sequencer(input).join(" ")
+    }
+}
+impl<'a, F: CharFilter> TokenizerPos<'a> for Pipeline<F> {
+    type TokenIter = std::vec::IntoIter<TokenPositional<'a>>;
+
+    fn tokenize_pos(&self, input: &'a str) -> Self::TokenIter {
+        CharTokenPosIter::new(&self.splitter, input)
+            .filter_map(|t| {
+                let (start_offset, position) = (t.start_offset, t.position);
+                self.apply(t.term).map(|term| TokenPositional {
+                    term,
+                    start_offset,
+                    position,
+                })
+            })
+            .collect::<Vec<TokenPositional<'a>>>()
+            .into_iter()
+    }
+
+    fn pos_summary(&self, tokens: Vec<TokenPositional>) -> TokenizerPosSummary {
+        // the filters already cased the terms
+        TokenizerPosSummary::from_tokens(tokens, |t| t.into_owned())
+    }
+}
+impl TokenFilter for Lowercase {
+    fn filter<'a>(&self, token: Cow<'a, str>) -> Option<Cow<'a, str>> {
+        if token.chars().any(char::is_uppercase) {
+            Some(token.to_lowercase().into())
+        } else {
+            Some(token)
+        }
+    }
+}
+impl TokenFilter for Normalizer {
+    fn filter<'a>(&self, token: Cow<'a, str>) -> Option<Cow<'a, str>> {
+        match token {
+            Cow::Borrowed(t) => Some(self.normalize(t)),
+            Cow::Owned(t) => Some(self.normalize(&t).into_owned().into()),
+        }
+    }
+}
+impl Stopwords {
+    pub fn new(words: &[&str]) -> Self {
+        Stopwords {
+            words: words.iter().map(|w| w.to_string()).collect(),
+        }
+    }
+}
+impl TokenFilter for Stopwords {
+    fn filter<'a>(&self, token: Cow<'a, str>) -> Option<Cow<'a, str>> {
+        if self.words.contains(token.as_ref()) {
+            None
+        } else {
+            Some(token)
+        }
+    }
+}
+impl TokenFilter for SStemmer {
+    fn filter<'a>(&self, token: Cow<'a, str>) -> Option<Cow<'a, str>> {
+        if token.chars().count() <= 3 {
+            return Some(token);
+        }
+        let ends = |suffixes: &[&str]| suffixes.iter().any(|s| token.ends_with(s));
+        // (bytes to cut, replacement), only the first matching rule applies
+        let (cut, with) = if token.ends_with("ies") && !ends(&["eies", "aies"]) {
+            (3, "y")
+        } else if token.ends_with('s') && !ends(&["us", "ss"]) {
+            // also Harman's "es" -> "e" rule, which drops the same final "s"
+            (1, "")
+        } else {
+            return Some(token);
+        };
+        let keep = token.len() - cut;
+        Some(match token {
+            Cow::Borrowed(t) if with.is_empty() => Cow::from(&t[..keep]),
+            t => Cow::from(format!("{}{}", &t[..keep], with)),
+        })
+    }
+}
+impl Synonyms {
+    /// new: from `(token, canonical)` pairs.
+    pub fn new(pairs: &[(&str, &str)]) -> Self {
+        Synonyms {
+            map: pairs
+                .iter()
+                .map(|(t, c)| (t.to_string(), c.to_string()))
+                .collect(),
+        }
+    }
+}
+impl TokenFilter for Synonyms {
+    fn filter<'a>(&self, token: Cow<'a, str>) -> Option<Cow<'a, str>> {
+        match self.map.get(token.as_ref()) {
+            Some(canonical) => Some(Cow::from(canonical.clone())),
+            None => Some(token),
+        }
+    }
+}
+impl TokenFilter for MinLength {
+    fn filter<'a>(&self, token: Cow<'a, str>) -> Option<Cow<'a, str>> {
+        if token.chars().count() < self.0 {
+            None
+        } else {
+            Some(token)
+        }
+    }
+}
+impl WhiteSpaceTokenizer {
+    /// is_edge_punctuation: sentence punctuation trimmed from the start and end of a token; '.' is
+    /// only trimmed from the end, so ".NET" keeps it.
+    fn is_edge_punctuation(c: char) -> bool {
+        ",;:!?\"()[]{}<>«»“”„".contains(c)
+    }
+    /// trim: `term` without edge punctuation and the number of chars trimmed from its start, `None` when nothing is left.
+    fn trim<'a>(term: Cow<'a, str>) -> Option<(usize, Cow<'a, str>)> {
+        let edge = WhiteSpaceTokenizer::is_edge_punctuation;
+        let trim = |t: &str| -> (usize, usize, usize) {
+            let start = t.trim_start_matches(edge);
+            let both = start.trim_end_matches(|c| edge(c) || c == '.');
+            let from = t.len() - start.len();
+            (t[..from].chars().count(), from, from + both.len())
+        };
+        let (lead, from, to) = trim(&term);
+        if from == to {
+            return None;
+        }
+        let trimmed = match term {
+            Cow::Borrowed(t) => Cow::from(&t[from..to]),
+            Cow::Owned(t) => Cow::from(t[from..to].to_string()),
+        };
+        Some((lead, trimmed))
+    }
+    fn trim_seq(t: TokenSequence<'_>) -> Option<TokenSequence<'_>> {
+        WhiteSpaceTokenizer::trim(t.term).map(|(_, term)| TokenSequence { term })
+    }
+    fn trim_pos(t: TokenPositional<'_>) -> Option<TokenPositional<'_>> {
+        let (start_offset, position) = (t.start_offset, t.position);
+        WhiteSpaceTokenizer::trim(t.term).map(|(lead, term)| TokenPositional {
+            term,
+            start_offset: start_offset + lead,
+            position,
+        })
+    }
+}
+impl<'a> TokenizerPos<'a> for WhiteSpaceTokenizer {
+    type TokenIter = WhiteSpacePosIter<'a>;
+
+    fn tokenize_pos(&self, input: &'a str) -> Self::TokenIter {
+        CharTokenPosIter::new(WhiteSpaceCharFilter, input).filter_map(WhiteSpaceTokenizer::trim_pos)
+    }
+
+    fn pos_summary(&self, tokens: Vec<TokenPositional>) -> TokenizerPosSummary {
+        TokenizerPosSummary::from_tokens(tokens, |t| t.to_lowercase())
+    }
+}
+impl<'a> TokenizerSeq<'a> for WhiteSpaceTokenizer {
+    type TokenIter = WhiteSpaceSeqIter<'a>;
+
+    fn tokenize_seq(&self, input: &'a str) -> Self::TokenIter {
+        CharTokenSeqIter::new(WhiteSpaceCharFilter, input).filter_map(WhiteSpaceTokenizer::trim_seq)
+    }
+
+    fn sequencer(&self, input: &'a str) -> Vec<std::borrow::Cow<'a, str>> {
+        self.tokenize_seq(input).map(|t| t.to_lower_cow()).collect()
+    }
+
+    fn token(&self, input: &'a str) -> String {
+        self.sequencer(input).join(" ")
+    }
+}
 impl<'a> TokenPositional<'a> {
     #[inline]
     pub fn convert_str(t: &'a str, start_offset: usize, position: usize) -> Self {
         TokenPositional {
             term: t.into(),
-            start_offset: start_offset,
-            position: position,
+            start_offset,
+            position,
         }
     }
     pub fn to_lower_cow(&self) -> Cow<'a, str> {
@@ -577,10 +940,6 @@ where
     fn u8_max(&mut self) -> u8 {
         self.fold(0, u8::max)
     }
-
-    fn u8_min(&mut self) -> u8 {
-        self.fold(0, u8::min)
-    }
 }
 
 //////////////////////////////////////////////////////////////////////
@@ -593,10 +952,7 @@ mod tests {
     #[test]
     fn on_word_splitter() {
         fn word_split(c: char) -> bool {
-            match c {
-                '\n' | '|' | '-' => true,
-                _ => false,
-            }
+            matches!(c, '\n' | '|' | '-')
         }
         let res = TokenizerNaive::word_splitter("HelLo|tHere", &word_split);
         assert_eq!(res, vec!["HelLo", "tHere"])
@@ -604,10 +960,7 @@ mod tests {
     #[test]
     fn on_tokens_lower_filter() {
         fn tokens_filter(c: char) -> bool {
-            match c {
-                '-' | '|' | '*' | ')' | '(' | '&' => true,
-                _ => false,
-            }
+            matches!(c, '-' | '|' | '*' | ')' | '(' | '&')
         }
         let res = TokenizerNaive::tokens_lower_with_filter("|HelLo tHere", &tokens_filter);
         assert_eq!(res, " hello there");
@@ -703,7 +1056,13 @@ mod tests {
         let s1 = "Marriot Beaches Resort foo";
         let s2 = "Beaches Resort Marriot bar";
         assert_eq!(TokenCmp::new_from_str(s1, s2).similarity(), 62);
-        let sim = token_sort(s1, s2, &TokenCmp::new_sort, &TokenCmp::similarity);
+        let sim = token_sort(
+            s1,
+            s2,
+            &AlphaNumericTokenizer,
+            &TokenCmp::new_sort,
+            &TokenCmp::similarity,
+        );
         assert_eq!(sim, 87);
     }
     #[test]
@@ -711,7 +1070,13 @@ mod tests {
         let s1 = "great is scala";
         let s2 = "java is great";
         assert_eq!(TokenCmp::new_from_str(s1, s2).similarity(), 37);
-        let sim = token_sort(s1, s2, &TokenCmp::new_sort_join, &TokenCmp::similarity);
+        let sim = token_sort(
+            s1,
+            s2,
+            &AlphaNumericTokenizer,
+            &TokenCmp::new_sort_join,
+            &TokenCmp::similarity,
+        );
         assert_eq!(sim, 81);
     }
     #[test]
@@ -721,7 +1086,13 @@ mod tests {
         assert_eq!(TokenCmp::new_from_str(sabre, ean).similarity(), 20);
         assert_eq!(TokenCmp::new_from_str(sabre, ean).partial_similarity(), 14);
         assert_eq!(
-            token_sort(sabre, ean, &TokenCmp::new_sort, &TokenCmp::similarity),
+            token_sort(
+                sabre,
+                ean,
+                &AlphaNumericTokenizer,
+                &TokenCmp::new_sort,
+                &TokenCmp::similarity
+            ),
             79
         );
 
@@ -729,6 +1100,7 @@ mod tests {
             token_sort(
                 sabre,
                 ean,
+                &AlphaNumericTokenizer,
                 &TokenCmp::new_sort,
                 &TokenCmp::partial_similarity
             ),
@@ -760,6 +1132,20 @@ mod tests {
         assert_eq!(sim2, 77);
     }
 
+    #[test]
+    fn on_token_cmp_opcodes() {
+        use crate::ratcliff_obershelp::Tag;
+        let tc = TokenCmp::new_from_str("Zürich HB", "Zürich Hbf");
+        assert_eq!(tc.matching_blocks(), vec![(0, 0, 8), (9, 10, 0)]);
+        let tags: Vec<Tag> = tc.opcodes().iter().map(|o| o.tag).collect();
+        assert_eq!(tags, vec![Tag::Equal, Tag::Replace]);
+        assert_eq!(tc.similarity(), 84);
+        assert_eq!(
+            TokenCmp::new_from_str("Café", "Café du Port").partial_similarity(),
+            100
+        );
+    }
+
     /*
 
     #[test]
@@ -832,4 +1218,183 @@ mod tests {
         );
     }
     */
+
+    #[test]
+    fn on_pre_process_with() {
+        let n = Normalizer::default();
+        assert_eq!(
+            TokenizerNaive::pre_process_with("Inbursa Cancún Las Américas", &n),
+            "inbursa cancun las americas"
+        );
+        assert_eq!(
+            TokenizerNaive::pre_process_with("Hotel Zu\u{308}rich", &n),
+            TokenizerNaive::pre_process_with("HOTEL ZÜRICH", &n)
+        );
+        assert_eq!(
+            TokenizerNaive::pre_process_with("Ｇｒａｎｄ Hôtel ﬁnal", &n),
+            "grand hotel final"
+        );
+    }
+
+    #[test]
+    fn on_normalized_tokenizer() {
+        // NFD combining marks are not alphanumeric, so the plain tokenizer splits inside the word
+        let an = AlphaNumericTokenizer;
+        assert_eq!(
+            an.sequencer("Zu\u{308}rich Marriott"),
+            vec!["zu", "rich", "marriott"]
+        );
+
+        let nt = NormalizedTokenizer::new(AlphaNumericTokenizer, Normalizer::default());
+        assert_eq!(
+            nt.sequencer("Zu\u{308}rich Marriott"),
+            vec!["zurich", "marriott"]
+        );
+        assert_eq!(nt.sequencer("ZÜRICH MARRIOTT"), vec!["zurich", "marriott"]);
+        assert_eq!(nt.token("Straße 5"), "strasse 5");
+        assert_eq!(
+            nt.tokenize_seq("Café ﬂore")
+                .map(|t| t.to_string())
+                .collect::<Vec<String>>(),
+            vec!["cafe", "flore"]
+        );
+    }
+
+    #[test]
+    fn on_pipeline_filters() {
+        let tokenizer = TokenizerBuilder::new(AlphaNumericCharFilter)
+            .lowercase()
+            .stopwords(&["the", "and"])
+            .synonyms(&[("intl", "international"), ("apts", "apartments")])
+            .stem()
+            .min_length(2)
+            .build();
+        assert_eq!(
+            tokenizer.sequencer("The Grand Intl Apts and Suites, Floor 3"),
+            vec!["grand", "international", "apartment", "suite", "floor"]
+        );
+        assert_eq!(
+            tokenizer.token("Holiday Properties|Classes"),
+            "holiday property classe"
+        );
+
+        // nothing is lowercased without a filter
+        let bare = Pipeline::builder(AlphaNumericCharFilter).build();
+        assert_eq!(bare.sequencer("Hotel & Spa"), vec!["Hotel", "Spa"]);
+    }
+
+    #[test]
+    fn on_pipeline_positional() {
+        let tokenizer = Pipeline::builder(AlphaNumericCharFilter)
+            .lowercase()
+            .stopwords(&["de"])
+            .build();
+        let summary = tokenizer.pos_summary(tokenizer.tokenize_pos("Hôtel de Ville").collect());
+        assert_eq!(summary.sequence, "hôtel ville");
+        assert_eq!(summary.offsets, vec![0, 9]);
+        assert_eq!(summary.positions, vec![0, 2]);
+    }
+
+    #[test]
+    fn on_pipeline_token_sort_set() {
+        let tokenizer = Pipeline::builder(AlphaNumericCharFilter)
+            .normalize(Normalizer::default())
+            .stopwords(&["the", "hotel"])
+            .stem()
+            .build();
+        let (s1, s2) = ("The Zürich Marriott Hotel Suites", "marriott suite zurich");
+        assert!(
+            token_sort(
+                s1,
+                s2,
+                &AlphaNumericTokenizer,
+                &TokenCmp::new_sort,
+                &TokenCmp::similarity
+            ) < 100
+        );
+        assert_eq!(
+            token_sort(
+                s1,
+                s2,
+                &tokenizer,
+                &TokenCmp::new_sort,
+                &TokenCmp::similarity
+            ),
+            100
+        );
+        assert!(token_set(s1, s2, &AlphaNumericTokenizer, &TokenCmp::similarity) < 100);
+        assert_eq!(token_set(s1, s2, &tokenizer, &TokenCmp::similarity), 100);
+    }
+
+    #[test]
+    fn on_white_space_tokenizer() {
+        assert!(WhiteSpaceCharFilter.is_char('\u{a0}'));
+        assert!(!WhiteSpaceCharFilter.is_char('-'));
+
+        let input = "Dell XPS-13 with 3.5mm jack,\u{3000}C++ SDK (O'Brien's pick).";
+        let ws = WhiteSpaceTokenizer;
+        assert_eq!(
+            ws.sequencer(input),
+            vec![
+                "dell",
+                "xps-13",
+                "with",
+                "3.5mm",
+                "jack",
+                "c++",
+                "sdk",
+                "o'brien's",
+                "pick"
+            ]
+        );
+        assert_eq!(
+            AlphaNumericTokenizer.token("C++ 3.5mm O'Brien XPS-13"),
+            "c 3 5mm o brien xps 13"
+        );
+        assert_eq!(
+            ws.token("C++ 3.5mm O'Brien XPS-13"),
+            "c++ 3.5mm o'brien xps-13"
+        );
+        assert_eq!(ws.token(".NET, -- \"\" ?"), ".net --");
+        assert_eq!(ws.sequencer("   ").len(), 0);
+    }
+
+    #[test]
+    fn on_white_space_positional() {
+        let ws = WhiteSpaceTokenizer;
+        let tokens: Vec<TokenPositional> = ws.tokenize_pos("«Zürich» (XPS-13) C++").collect();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<String>>(),
+            vec!["Zürich, 1, 0", "XPS-13, 10, 1", "C++, 18, 2"]
+        );
+        let summary = ws.pos_summary(tokens);
+        assert_eq!(summary.sequence, "zürich xps-13 c++");
+        assert_eq!(summary.offsets, vec![1, 10, 18]);
+    }
+
+    #[test]
+    fn on_token_sort_set_tokenizer() {
+        let (s1, s2) = ("C++ Primer 5th Edition", "Edition 5th C Primer");
+        let an = AlphaNumericTokenizer;
+        let ws = WhiteSpaceTokenizer;
+        assert_eq!(
+            token_sort(s1, s2, &an, &TokenCmp::new_sort_join, &TokenCmp::similarity),
+            100
+        );
+        assert!(token_sort(s1, s2, &ws, &TokenCmp::new_sort_join, &TokenCmp::similarity) < 100);
+        assert_eq!(token_set(s1, s2, &an, &TokenCmp::similarity), 100);
+        assert!(token_set(s1, s2, &ws, &TokenCmp::similarity) < 100);
+        assert_eq!(
+            token_set(
+                "XPS-13 Dell",
+                "Dell XPS-13 laptop",
+                &ws,
+                &TokenCmp::partial_similarity
+            ),
+            100
+        );
+    }
 }
@@ -0,0 +1,499 @@
+use crate::metric::StringMetric;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// CostModel supplies the edit costs used by `weighted_levenshtein_distance`.
+/// Costs should be non-negative; a substitution of a char by itself is always free.
+pub trait CostModel {
+    /// cost of substituting char `a` in the source by char `b` in the target.
+    fn substitution_cost(&self, a: char, b: char) -> f64;
+    /// cost of inserting char `c` into the source.
+    fn insertion_cost(&self, c: char) -> f64;
+    /// cost of deleting char `c` from the source.
+    fn deletion_cost(&self, c: char) -> f64;
+    /// multi-char substitutions, such as "rn" -> "m", considered in addition to the single char edits.
+    fn multi_char_substitutions(&self) -> &[MultiCharSubstitution] {
+        &[]
+    }
+}
+
+/// MultiCharSubstitution replaces the char sequence `from` in the source by `to` in the target at `cost`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiCharSubstitution {
+    pub from: Vec<char>,
+    pub to: Vec<char>,
+    pub cost: f64,
+}
+
+/// CostTable is a lookup `CostModel`: per char pair substitution costs, per char insertion/deletion costs
+/// and multi-char substitutions, falling back to the default costs for anything not in the table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostTable {
+    pub default_substitution: f64,
+    pub default_insertion: f64,
+    pub default_deletion: f64,
+    substitutions: HashMap<(char, char), f64>,
+    insertions: HashMap<char, f64>,
+    deletions: HashMap<char, f64>,
+    multi: Vec<MultiCharSubstitution>,
+}
+
+/// CostTableError for handling errors when reading a `CostTable` back from disk.
+#[derive(Debug)]
+pub enum CostTableError {
+    Io(std::io::Error),
+    /// line number (1-based) that could not be parsed.
+    Parse(usize),
+}
+/// fmt for CostTableError
+impl std::fmt::Display for CostTableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            CostTableError::Io(ref e) => write!(f, "ERROR: cost table io: {}", e),
+            CostTableError::Parse(line) => {
+                write!(f, "ERROR: cost table malformed at line {}", line)
+            }
+        }
+    }
+}
+impl From<std::io::Error> for CostTableError {
+    fn from(e: std::io::Error) -> Self {
+        CostTableError::Io(e)
+    }
+}
+
+impl CostTable {
+    pub fn new(default_substitution: f64, default_insertion: f64, default_deletion: f64) -> Self {
+        CostTable {
+            default_substitution,
+            default_insertion,
+            default_deletion,
+            substitutions: HashMap::new(),
+            insertions: HashMap::new(),
+            deletions: HashMap::new(),
+            multi: Vec::new(),
+        }
+    }
+
+    /// set_substitution: cost of substituting `a` by `b`, one direction only.
+    pub fn set_substitution(&mut self, a: char, b: char, cost: f64) {
+        self.substitutions.insert((a, b), cost);
+    }
+
+    /// set_confusion: cost of substituting `a` by `b` and `b` by `a`.
+    pub fn set_confusion(&mut self, a: char, b: char, cost: f64) {
+        self.set_substitution(a, b, cost);
+        self.set_substitution(b, a, cost);
+    }
+
+    pub fn set_insertion(&mut self, c: char, cost: f64) {
+        self.insertions.insert(c, cost);
+    }
+
+    pub fn set_deletion(&mut self, c: char, cost: f64) {
+        self.deletions.insert(c, cost);
+    }
+
+    /// add_multi_substitution: cost of substituting the chars of `from` by the chars of `to`, one direction only.
+    pub fn add_multi_substitution(&mut self, from: &str, to: &str, cost: f64) {
+        self.multi.push(MultiCharSubstitution {
+            from: from.chars().collect(),
+            to: to.chars().collect(),
+            cost,
+        });
+    }
+
+    /// ocr_confusions: unit costs with cheap substitutions for look-alike chars common in OCR output
+    /// (`0`/`O`, `1`/`l`/`I`, `5`/`S`, ...), cheap insertion/deletion of specks read as punctuation,
+    /// and multi-char look-alikes such as `rn`/`m` and `cl`/`d`.
+    pub fn ocr_confusions() -> Self {
+        let mut table = CostTable::new(1.0, 1.0, 1.0);
+        let look_alikes = [
+            ('0', 'O'),
+            ('0', 'o'),
+            ('0', 'D'),
+            ('0', 'Q'),
+            ('O', 'D'),
+            ('O', 'Q'),
+            ('1', 'l'),
+            ('1', 'I'),
+            ('1', '|'),
+            ('1', '7'),
+            ('l', 'I'),
+            ('l', '|'),
+            ('I', '|'),
+            ('2', 'Z'),
+            ('2', 'z'),
+            ('5', 'S'),
+            ('5', 's'),
+            ('6', 'G'),
+            ('6', 'b'),
+            ('8', 'B'),
+            ('9', 'g'),
+            ('9', 'q'),
+            ('4', 'A'),
+            ('c', 'e'),
+            ('u', 'v'),
+            ('n', 'h'),
+        ];
+        for &(a, b) in look_alikes.iter() {
+            table.set_confusion(a, b, 0.25);
+        }
+        for &c in ['.', ',', '\'', '`', ':', ';'].iter() {
+            table.set_insertion(c, 0.5);
+            table.set_deletion(c, 0.5);
+        }
+        for &(a, b) in [
+            ("rn", "m"),
+            ("cl", "d"),
+            ("vv", "w"),
+            ("VV", "W"),
+            ("nn", "m"),
+        ]
+        .iter()
+        {
+            table.add_multi_substitution(a, b, 0.25);
+            table.add_multi_substitution(b, a, 0.25);
+        }
+        table
+    }
+
+    /// write_to: serializes the table as tab separated lines, one cost per line:
+    ///
+    /// ```text
+    /// default <sub> <ins> <del>
+    /// sub     <a> <b> <cost>
+    /// ins     <c> <cost>
+    /// del     <c> <cost>
+    /// multi   <from> <to> <cost>
+    /// ```
+    ///
+    /// tab, newline, carriage return and backslash in chars are written as `\t`, `\n`, `\r` and `\\`.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> std::io::Result<()> {
+        writeln!(
+            w,
+            "default\t{}\t{}\t{}",
+            self.default_substitution, self.default_insertion, self.default_deletion
+        )?;
+        let mut subs: Vec<_> = self.substitutions.iter().collect();
+        subs.sort_by(|x, y| x.0.cmp(y.0));
+        for (&(a, b), cost) in subs {
+            writeln!(w, "sub\t{}\t{}\t{}", escape(&[a]), escape(&[b]), cost)?;
+        }
+        let mut ins: Vec<_> = self.insertions.iter().collect();
+        ins.sort_by(|x, y| x.0.cmp(y.0));
+        for (&c, cost) in ins {
+            writeln!(w, "ins\t{}\t{}", escape(&[c]), cost)?;
+        }
+        let mut dels: Vec<_> = self.deletions.iter().collect();
+        dels.sort_by(|x, y| x.0.cmp(y.0));
+        for (&c, cost) in dels {
+            writeln!(w, "del\t{}\t{}", escape(&[c]), cost)?;
+        }
+        for m in &self.multi {
+            writeln!(
+                w,
+                "multi\t{}\t{}\t{}",
+                escape(&m.from),
+                escape(&m.to),
+                m.cost
+            )?;
+        }
+        Ok(())
+    }
+
+    /// read_from: parses a table written by `write_to`; blank lines are skipped.
+    pub fn read_from<R: BufRead>(r: R) -> Result<Self, CostTableError> {
+        let mut table = CostTable::new(1.0, 1.0, 1.0);
+        for (n, line) in r.lines().enumerate() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let err = CostTableError::Parse(n + 1);
+            let cost = |s: &str| s.parse::<f64>().map_err(|_| CostTableError::Parse(n + 1));
+            let one_char = |s: &str| match unescape(s)?[..] {
+                [c] => Some(c),
+                _ => None,
+            };
+            match fields[..] {
+                ["default", sub, ins, del] => {
+                    table.default_substitution = cost(sub)?;
+                    table.default_insertion = cost(ins)?;
+                    table.default_deletion = cost(del)?;
+                }
+                ["sub", a, b, c] => match (one_char(a), one_char(b)) {
+                    (Some(a), Some(b)) => table.set_substitution(a, b, cost(c)?),
+                    _ => return Err(err),
+                },
+                ["ins", a, c] => match one_char(a) {
+                    Some(a) => table.set_insertion(a, cost(c)?),
+                    None => return Err(err),
+                },
+                ["del", a, c] => match one_char(a) {
+                    Some(a) => table.set_deletion(a, cost(c)?),
+                    None => return Err(err),
+                },
+                ["multi", from, to, c] => match (unescape(from), unescape(to)) {
+                    (Some(from), Some(to)) => table.multi.push(MultiCharSubstitution {
+                        from,
+                        to,
+                        cost: cost(c)?,
+                    }),
+                    _ => return Err(err),
+                },
+                _ => return Err(err),
+            }
+        }
+        Ok(table)
+    }
+
+    /// save: `write_to` the file at `path`, creating or truncating it.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_to(&mut w)?;
+        w.flush()
+    }
+
+    /// load: `read_from` the file at `path`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CostTableError> {
+        CostTable::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+/// escape: chars to a single tab separated field.
+fn escape(chars: &[char]) -> String {
+    let mut s = String::with_capacity(chars.len());
+    for &c in chars {
+        match c {
+            '\t' => s.push_str("\\t"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\\' => s.push_str("\\\\"),
+            _ => s.push(c),
+        }
+    }
+    s
+}
+
+/// unescape: reverses `escape`, None on an unknown escape sequence.
+fn unescape(field: &str) -> Option<Vec<char>> {
+    let mut chars = Vec::with_capacity(field.len());
+    let mut it = field.chars();
+    while let Some(c) = it.next() {
+        if c != '\\' {
+            chars.push(c);
+            continue;
+        }
+        match it.next() {
+            Some('t') => chars.push('\t'),
+            Some('n') => chars.push('\n'),
+            Some('r') => chars.push('\r'),
+            Some('\\') => chars.push('\\'),
+            _ => return None,
+        }
+    }
+    Some(chars)
+}
+
+impl CostModel for CostTable {
+    fn substitution_cost(&self, a: char, b: char) -> f64 {
+        *self
+            .substitutions
+            .get(&(a, b))
+            .unwrap_or(&self.default_substitution)
+    }
+    fn insertion_cost(&self, c: char) -> f64 {
+        *self.insertions.get(&c).unwrap_or(&self.default_insertion)
+    }
+    fn deletion_cost(&self, c: char) -> f64 {
+        *self.deletions.get(&c).unwrap_or(&self.default_deletion)
+    }
+    fn multi_char_substitutions(&self) -> &[MultiCharSubstitution] {
+        &self.multi
+    }
+}
+
+/// weighted_levenshtein_distance: minimum total cost of insertions, deletions, substitutions and
+/// multi-char substitutions from `costs` to turn `s1` into `s2`. higher score is less similar.
+/// [edit distance wikipedia](https://en.wikipedia.org/wiki/Edit_distance#General_algorithm)
+pub fn weighted_levenshtein_distance<C: CostModel + ?Sized>(s1: &str, s2: &str, costs: &C) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    weighted_levenshtein(&a, &b, costs)
+}
+
+/// weighted_levenshtein_similarity: 1 - weighted_levenshtein_distance / the cost of the cheaper of two worst-case
+/// edit paths: substitute every aligned char pair, identical or not, then delete or insert the rest, or delete all
+/// of `s1` then insert all of `s2`. Both bound the distance so the result is within [0,1], and unit costs give
+/// `levenshtein_similarity`. higher score is more similar.
+pub fn weighted_levenshtein_similarity<C: CostModel + ?Sized>(
+    s1: &str,
+    s2: &str,
+    costs: &C,
+) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (s1.chars().collect(), s2.chars().collect());
+    let deletions = |chars: &[char]| chars.iter().map(|&c| costs.deletion_cost(c)).sum::<f64>();
+    let insertions = |chars: &[char]| chars.iter().map(|&c| costs.insertion_cost(c)).sum::<f64>();
+    let shared = a.len().min(b.len());
+    let substituted = a
+        .iter()
+        .zip(b.iter())
+        .map(|(&x, &y)| costs.substitution_cost(x, y))
+        .sum::<f64>()
+        + deletions(&a[shared..])
+        + insertions(&b[shared..]);
+    let worst = substituted.min(deletions(&a) + insertions(&b));
+    if worst == 0.0 {
+        return 1.0;
+    }
+    1.0 - weighted_levenshtein(&a, &b, costs) / worst
+}
+
+/// weighted_levenshtein over the full dp matrix; multi-char substitutions look back more than one row/column.
+fn weighted_levenshtein<C: CostModel + ?Sized>(a: &[char], b: &[char], costs: &C) -> f64 {
+    let cols = b.len() + 1;
+    let mut d = vec![0.0; (a.len() + 1) * cols];
+    let idx = |i: usize, j: usize| i * cols + j;
+
+    for i in 1..=a.len() {
+        d[idx(i, 0)] = d[idx(i - 1, 0)] + costs.deletion_cost(a[i - 1]);
+    }
+    for j in 1..=b.len() {
+        d[idx(0, j)] = d[idx(0, j - 1)] + costs.insertion_cost(b[j - 1]);
+    }
+    let multi = costs.multi_char_substitutions();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let (x, y) = (a[i - 1], b[j - 1]);
+            let substitution = if x == y {
+                0.0
+            } else {
+                costs.substitution_cost(x, y)
+            };
+            let mut best = (d[idx(i - 1, j - 1)] + substitution)
+                .min(d[idx(i - 1, j)] + costs.deletion_cost(x))
+                .min(d[idx(i, j - 1)] + costs.insertion_cost(y));
+            for m in multi {
+                let (fl, tl) = (m.from.len(), m.to.len());
+                if fl + tl > 0
+                    && fl <= i
+                    && tl <= j
+                    && a[i - fl..i] == m.from[..]
+                    && b[j - tl..j] == m.to[..]
+                {
+                    best = best.min(d[idx(i - fl, j - tl)] + m.cost);
+                }
+            }
+            d[idx(i, j)] = best;
+        }
+    }
+    d[idx(a.len(), b.len())]
+}
+
+/// WeightedLevenshtein implements `StringMetric` with `weighted_levenshtein_similarity` for the cost model `costs`;
+/// the raw score is the weighted edit cost.
+pub struct WeightedLevenshtein<C: CostModel> {
+    pub costs: C,
+}
+
+impl<C: CostModel> StringMetric for WeightedLevenshtein<C> {
+    fn score(&self, s1: &str, s2: &str) -> f64 {
+        weighted_levenshtein_distance(s1, s2, &self.costs)
+    }
+    fn similarity(&self, s1: &str, s2: &str) -> f64 {
+        weighted_levenshtein_similarity(s1, s2, &self.costs)
+    }
+}
+
+#[allow(clippy::float_cmp)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_distance::{levenshtein_distance, levenshtein_similarity};
+
+    #[test]
+    fn on_unit_costs_match_levenshtein() {
+        let unit = CostTable::new(1.0, 1.0, 1.0);
+        for &(a, b) in &[
+            ("kitten", "sitting"),
+            ("", "abc"),
+            ("Zürich", "Zurich"),
+            ("ab", "ba"),
+        ] {
+            assert_eq!(
+                weighted_levenshtein_distance(a, b, &unit),
+                levenshtein_distance(a, b) as f64
+            );
+            assert_eq!(
+                weighted_levenshtein_similarity(a, b, &unit),
+                levenshtein_similarity(a, b)
+            );
+        }
+    }
+
+    #[test]
+    fn on_ocr_confusions() {
+        let ocr = CostTable::ocr_confusions();
+        assert_eq!(
+            weighted_levenshtein_distance("POLICY 5O1", "POLICY 501", &ocr),
+            0.25
+        );
+        assert_eq!(weighted_levenshtein_distance("I0O", "100", &ocr), 0.5);
+        assert_eq!(
+            weighted_levenshtein_distance("modern", "rnodern", &ocr),
+            0.25
+        );
+        assert_eq!(weighted_levenshtein_distance("Bldg.", "Bldg", &ocr), 0.5);
+        assert_eq!(weighted_levenshtein_distance("X", "Y", &ocr), 1.0);
+        assert!(
+            weighted_levenshtein_similarity("5ALES", "SALES", &ocr)
+                > weighted_levenshtein_similarity("KALES", "SALES", &ocr)
+        );
+    }
+
+    #[test]
+    fn on_directional_costs() {
+        let mut t = CostTable::new(1.0, 1.0, 1.0);
+        t.set_substitution('a', 'b', 0.1);
+        t.set_deletion('x', 0.2);
+        t.set_insertion('y', 0.3);
+        assert_eq!(weighted_levenshtein_distance("a", "b", &t), 0.1);
+        assert_eq!(weighted_levenshtein_distance("b", "a", &t), 1.0);
+        assert_eq!(weighted_levenshtein_distance("ax", "a", &t), 0.2);
+        assert_eq!(weighted_levenshtein_distance("a", "ay", &t), 0.3);
+    }
+
+    #[test]
+    fn on_weighted_metric() {
+        let w = WeightedLevenshtein {
+            costs: CostTable::ocr_confusions(),
+        };
+        assert_eq!(w.similarity("", ""), 1.0);
+        assert_eq!(w.similarity("abc", "abc"), 1.0);
+        assert_eq!(w.similarity("abc", "xyz"), 0.0);
+        assert_eq!(w.score("c1ose", "close"), 0.25);
+    }
+
+    #[test]
+    fn on_cost_table_roundtrip() {
+        let mut t = CostTable::ocr_confusions();
+        t.set_substitution('\t', '\\', 0.125);
+        t.set_deletion('\n', 2.5);
+        let mut buf: Vec<u8> = Vec::new();
+        t.write_to(&mut buf).unwrap();
+        let back = CostTable::read_from(&buf[..]).unwrap();
+        assert_eq!(back, t);
+    }
+
+    #[test]
+    fn on_cost_table_malformed() {
+        let bad = "default\t1\t1\t1\nsub\tab\tc\t0.5\n";
+        match CostTable::read_from(bad.as_bytes()) {
+            Err(CostTableError::Parse(line)) => assert_eq!(line, 2),
+            _ => panic!("expected parse error"),
+        }
+    }
+}